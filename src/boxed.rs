@@ -0,0 +1,480 @@
+use syn::parse_quote;
+use syn::parse_quote_spanned;
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::visit_mut::VisitMut;
+
+/// Name the elided lifetimes of a method signature.
+///
+/// Elided lifetimes inside function pointers and `Fn` traits are left
+/// untouched, since they are higher-ranked and not part of the signature.
+struct LifetimeElider {
+    lifetimes: Vec<syn::Lifetime>,
+}
+
+impl LifetimeElider {
+    fn next_lifetime(&mut self, span: proc_macro2::Span) -> syn::Lifetime {
+        let lifetime = syn::Lifetime::new(&format!("'life{}", self.lifetimes.len()), span);
+        self.lifetimes.push(lifetime.clone());
+        lifetime
+    }
+}
+
+impl VisitMut for LifetimeElider {
+    fn visit_receiver_mut(&mut self, r: &mut syn::Receiver) {
+        if let Some((_, ref mut lifetime)) = r.reference {
+            match lifetime {
+                None => *lifetime = Some(self.next_lifetime(r.self_token.span)),
+                Some(l) if l.ident == "_" => *l = self.next_lifetime(l.span()),
+                Some(_) => (),
+            }
+            // the receiver type must stay `&Self`, `&mut Self` or `Self`
+            if r.colon_token.is_none() {
+                let lifetime = lifetime.as_ref();
+                let mutability = &r.mutability;
+                *r.ty = parse_quote!(&#lifetime #mutability Self);
+            }
+        }
+    }
+
+    fn visit_type_reference_mut(&mut self, r: &mut syn::TypeReference) {
+        if r.lifetime.is_none() {
+            r.lifetime = Some(self.next_lifetime(r.and_token.span));
+        }
+        syn::visit_mut::visit_type_reference_mut(self, r);
+    }
+
+    fn visit_lifetime_mut(&mut self, l: &mut syn::Lifetime) {
+        if l.ident == "_" {
+            *l = self.next_lifetime(l.span());
+        }
+    }
+
+    fn visit_type_bare_fn_mut(&mut self, _f: &mut syn::TypeBareFn) {}
+
+    fn visit_parenthesized_generic_arguments_mut(
+        &mut self,
+        _a: &mut syn::ParenthesizedGenericArguments,
+    ) {
+    }
+}
+
+/// Name the elided lifetimes of a return type after the elision rules.
+struct OutputElider {
+    lifetime: syn::Lifetime,
+}
+
+impl VisitMut for OutputElider {
+    fn visit_type_reference_mut(&mut self, r: &mut syn::TypeReference) {
+        if r.lifetime.is_none() {
+            r.lifetime = Some(self.lifetime.clone());
+        }
+        syn::visit_mut::visit_type_reference_mut(self, r);
+    }
+
+    fn visit_lifetime_mut(&mut self, l: &mut syn::Lifetime) {
+        if l.ident == "_" {
+            *l = self.lifetime.clone();
+        }
+    }
+
+    fn visit_type_bare_fn_mut(&mut self, _f: &mut syn::TypeBareFn) {}
+
+    fn visit_parenthesized_generic_arguments_mut(
+        &mut self,
+        _a: &mut syn::ParenthesizedGenericArguments,
+    ) {
+    }
+}
+
+/// Name the elided lifetimes of a signature and bind them to `lifetime`.
+///
+/// Every lifetime and type parameter of the method, as well as `Self` for
+/// methods with a receiver, is bound to outlive the given lifetime, which
+/// is declared as an additional lifetime parameter of the method.
+///
+/// Given `fn call(&self, x: &str)` and `'blanket`, get
+/// `fn call<'life0, 'life1, 'blanket>(&'life0 self, x: &'life1 str)`, with
+/// `'life0: 'blanket, 'life1: 'blanket, Self: 'blanket`.
+pub fn elaborate_signature_lifetimes(sig: &mut syn::Signature, lifetime: &syn::Lifetime) {
+    // name all elided lifetimes in the arguments
+    let mut elider = LifetimeElider {
+        lifetimes: Vec::new(),
+    };
+    for input in sig.inputs.iter_mut() {
+        match input {
+            syn::FnArg::Receiver(ref mut r) => elider.visit_receiver_mut(r),
+            syn::FnArg::Typed(ref mut t) => {
+                elider.visit_type_mut(&mut t.ty);
+                if let syn::Type::ImplTrait(ref mut impl_) = t.ty.as_mut() {
+                    impl_.bounds.push(parse_quote!(#lifetime));
+                }
+            }
+        }
+    }
+
+    // elided lifetimes of the return type refer to the receiver, or to the
+    // only lifetime of the arguments
+    let elided = match sig.receiver().and_then(|r| r.reference.as_ref()) {
+        Some((_, lifetime)) => lifetime.clone(),
+        None if elider.lifetimes.len() == 1 && sig.generics.lifetimes().count() == 0 => {
+            elider.lifetimes.first().cloned()
+        }
+        None => None,
+    };
+    if let (Some(lifetime), syn::ReturnType::Type(_, ref mut ty)) = (elided, &mut sig.output) {
+        OutputElider { lifetime }.visit_type_mut(ty);
+    }
+
+    // declare the new lifetimes and bind all generics to the given lifetime
+    let mut predicates: Vec<syn::WherePredicate> = Vec::new();
+    for param in sig.generics.params.iter() {
+        match param {
+            syn::GenericParam::Lifetime(l) => {
+                let l = &l.lifetime;
+                predicates.push(parse_quote!(#l: #lifetime));
+            }
+            syn::GenericParam::Type(t) => {
+                let t = &t.ident;
+                predicates.push(parse_quote!(#t: #lifetime));
+            }
+            syn::GenericParam::Const(_) => (),
+        }
+    }
+    // lifetimes must be declared before type parameters
+    let mut index = sig.generics.lifetimes().count();
+    for l in elider.lifetimes {
+        predicates.push(parse_quote!(#l: #lifetime));
+        sig.generics.params.insert(index, parse_quote!(#l));
+        index += 1;
+    }
+    sig.generics.params.insert(index, parse_quote!(#lifetime));
+    if sig.receiver().is_some() {
+        predicates.push(parse_quote!(Self: #lifetime));
+    }
+    sig.generics
+        .make_where_clause()
+        .predicates
+        .extend(predicates);
+    if sig.generics.lt_token.is_none() {
+        sig.generics.lt_token = Some(Default::default());
+        sig.generics.gt_token = Some(Default::default());
+    }
+}
+
+/// Rewrite an `async fn` signature to return a boxed future.
+///
+/// The lifetimes of the signature are elaborated so that the future can
+/// borrow from all of the arguments, and the future is given the additional
+/// `bounds`.
+///
+/// Given `async fn call(&self, x: &str) -> u32` and `Send`, get
+/// `fn call<'life0, 'life1, 'blanket>(&'life0 self, x: &'life1 str)
+/// -> Pin<Box<dyn Future<Output = u32> + Send + 'blanket>>`, with
+/// `'life0: 'blanket, 'life1: 'blanket, Self: 'blanket`.
+pub fn box_signature_future(
+    sig: &mut syn::Signature,
+    bounds: &Punctuated<syn::TypeParamBound, syn::Token![+]>,
+) {
+    let async_ = match sig.asyncness.take() {
+        Some(async_) => async_,
+        None => return,
+    };
+    let span = async_.span();
+    let lifetime = syn::Lifetime::new("'blanket", span);
+    elaborate_signature_lifetimes(sig, &lifetime);
+
+    // box the returned future
+    let output = match sig.output {
+        syn::ReturnType::Default => parse_quote!(()),
+        syn::ReturnType::Type(_, ref ty) => ty.as_ref().clone(),
+    };
+    let mut bounds = bounds.clone();
+    bounds.push(parse_quote!(#lifetime));
+    sig.output = parse_quote_spanned!(span=>
+        -> std::pin::Pin<std::boxed::Box<
+            dyn std::future::Future<Output = #output> + #bounds
+        >>
+    );
+}
+
+/// The arguments of `boxed_futures`.
+///
+/// The boxed futures are `Send` unless given `boxed_futures(?Send)`.
+pub struct BoxedFutures {
+    pub send: bool,
+}
+
+impl BoxedFutures {
+    pub fn from_meta(meta: &syn::Meta) -> syn::Result<Self> {
+        match meta {
+            syn::Meta::Path(_) => Ok(Self { send: true }),
+            syn::Meta::List(l) => {
+                let bound: syn::TraitBound = l.parse_args()?;
+                let maybe = matches!(bound.modifier, syn::TraitBoundModifier::Maybe(_));
+                if !maybe || !bound.path.is_ident("Send") {
+                    return Err(syn::Error::new(bound.span(), "expected `?Send`"));
+                }
+                Ok(Self { send: false })
+            }
+            syn::Meta::NameValue(_) => Err(syn::Error::new(meta.span(), "expected `?Send`")),
+        }
+    }
+
+    /// Get the bounds of the boxed futures.
+    fn bounds(&self) -> Punctuated<syn::TypeParamBound, syn::Token![+]> {
+        if self.send {
+            parse_quote!(std::marker::Send)
+        } else {
+            Punctuated::new()
+        }
+    }
+
+    /// Wrap the body of an `async fn` into a pinned box.
+    fn box_block(block: syn::Block) -> syn::Block {
+        let span = block.span();
+        parse_quote_spanned!(span=> {
+            std::boxed::Box::pin(async move #block)
+        })
+    }
+
+    /// Check that the default `async` bodies of `trait_` return `Send` futures.
+    ///
+    /// Default bodies capture the receiver in the future, so the trait must
+    /// require `Sync` for `&self` methods, and `Send` for `&mut self` and
+    /// `self` methods, for the boxed future to be `Send`.
+    pub fn check_trait_defaults(&self, trait_: &syn::ItemTrait) -> syn::Result<()> {
+        if !self.send {
+            return Ok(());
+        }
+        let has_supertrait = |name: &str| {
+            trait_.supertraits.iter().any(|bound| match bound {
+                syn::TypeParamBound::Trait(t) => {
+                    t.path.segments.last().is_some_and(|s| s.ident == name)
+                }
+                _ => false,
+            })
+        };
+        let mut errors: Option<syn::Error> = None;
+        for item in trait_.items.iter() {
+            let m = match item {
+                syn::TraitItem::Fn(m) if m.sig.asyncness.is_some() && m.default.is_some() => m,
+                _ => continue,
+            };
+            let required = match m.sig.receiver() {
+                Some(r) => match *r.ty {
+                    syn::Type::Reference(ref ty) if ty.mutability.is_none() => "Sync",
+                    _ => "Send",
+                },
+                None => continue,
+            };
+            if !has_supertrait(required) {
+                let msg = format!(
+                    "the default body of `{}` captures `self`, so its boxed future is only `Send` if `{}` is `{}`: add `{}` to the supertraits, or use `boxed_futures(?Send)`",
+                    m.sig.ident, trait_.ident, required, required
+                );
+                let e = syn::Error::new(m.sig.ident.span(), msg);
+                match errors {
+                    Some(ref mut err) => err.combine(e),
+                    None => errors = Some(e),
+                }
+            }
+        }
+        match errors {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    /// Update the `async fn` declarations of `trait_` to return boxed futures.
+    pub fn box_trait_futures(&self, mut trait_: syn::ItemTrait) -> syn::ItemTrait {
+        let bounds = self.bounds();
+        for item in trait_.items.iter_mut() {
+            if let syn::TraitItem::Fn(ref mut m) = item {
+                if m.sig.asyncness.is_none() {
+                    continue;
+                }
+                m.default = m.default.take().map(Self::box_block);
+                box_signature_future(&mut m.sig, &bounds);
+            }
+        }
+        trait_
+    }
+
+    /// Update the `async fn` definitions of `impl_` to return boxed futures.
+    ///
+    /// This gives the implementors of a trait with boxed futures the exact
+    /// signatures declared by the trait, without writing them by hand.
+    pub fn box_impl_futures(&self, mut impl_: syn::ItemImpl) -> syn::ItemImpl {
+        let bounds = self.bounds();
+        for item in impl_.items.iter_mut() {
+            if let syn::ImplItem::Fn(ref mut m) = item {
+                if m.sig.asyncness.is_none() {
+                    continue;
+                }
+                let block = std::mem::replace(&mut m.block, parse_quote!({}));
+                m.block = Self::box_block(block);
+                box_signature_future(&mut m.sig, &bounds);
+            }
+        }
+        impl_
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use syn::parse_quote;
+
+    #[test]
+    #[rustfmt::skip]
+    fn box_signature_future() {
+        let mut sig = parse_quote!(async fn call(&self, x: &str, y: Vec<&'_ u8>) -> u32);
+        super::box_signature_future(&mut sig, &parse_quote!(std::marker::Send));
+        assert_eq!(
+            sig,
+            parse_quote!(
+                fn call<'life0, 'life1, 'life2, 'blanket>(
+                    &'life0 self,
+                    x: &'life1 str,
+                    y: Vec<&'life2 u8>
+                ) -> std::pin::Pin<
+                    std::boxed::Box<
+                        dyn std::future::Future<Output = u32> + std::marker::Send + 'blanket
+                    >
+                >
+                where
+                    'life0: 'blanket,
+                    'life1: 'blanket,
+                    'life2: 'blanket,
+                    Self: 'blanket
+            )
+        );
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn box_signature_future_generics() {
+        let mut sig = parse_quote!(async fn call<'a, T: Clone>(x: &'a T, f: fn(&u8)) where T: Send);
+        super::box_signature_future(&mut sig, &parse_quote!(std::marker::Send));
+        assert_eq!(
+            sig,
+            parse_quote!(
+                fn call<'a, 'blanket, T: Clone>(
+                    x: &'a T,
+                    f: fn(&u8)
+                ) -> std::pin::Pin<
+                    std::boxed::Box<
+                        dyn std::future::Future<Output = ()> + std::marker::Send + 'blanket
+                    >
+                >
+                where
+                    T: Send,
+                    'a: 'blanket,
+                    T: 'blanket
+            )
+        );
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn box_trait_futures() {
+        let trait_ = parse_quote!(
+            trait Service: Sync {
+                async fn ready(&self) -> bool {
+                    true
+                }
+                fn name(&self) -> String;
+            }
+        );
+        let boxed = super::BoxedFutures { send: true };
+        assert_eq!(
+            boxed.box_trait_futures(trait_),
+            parse_quote!(
+                trait Service: Sync {
+                    fn ready<'life0, 'blanket>(
+                        &'life0 self
+                    ) -> std::pin::Pin<
+                        std::boxed::Box<
+                            dyn std::future::Future<Output = bool> + std::marker::Send + 'blanket
+                        >
+                    >
+                    where
+                        'life0: 'blanket,
+                        Self: 'blanket
+                    {
+                        std::boxed::Box::pin(async move { true })
+                    }
+                    fn name(&self) -> String;
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn check_trait_defaults() {
+        let boxed = super::BoxedFutures { send: true };
+        let trait_ = parse_quote!(
+            trait Service {
+                async fn ready(&self) -> bool {
+                    true
+                }
+                async fn reset(&mut self) {}
+                async fn call(&self);
+            }
+        );
+        let err = boxed.check_trait_defaults(&trait_).unwrap_err();
+        assert_eq!(err.into_iter().count(), 2);
+
+        let trait_ = parse_quote!(
+            trait Service: Send + Sync {
+                async fn ready(&self) -> bool {
+                    true
+                }
+                async fn reset(&mut self) {}
+            }
+        );
+        assert!(boxed.check_trait_defaults(&trait_).is_ok());
+
+        let trait_ = parse_quote!(
+            trait Service {
+                async fn ready(&self) -> bool {
+                    true
+                }
+            }
+        );
+        let local = super::BoxedFutures { send: false };
+        assert!(local.check_trait_defaults(&trait_).is_ok());
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn box_impl_futures() {
+        let impl_ = parse_quote!(
+            impl Service for Local {
+                async fn call(&self) -> u32 {
+                    self.0
+                }
+            }
+        );
+        let boxed = super::BoxedFutures { send: false };
+        assert_eq!(
+            boxed.box_impl_futures(impl_),
+            parse_quote!(
+                impl Service for Local {
+                    fn call<'life0, 'blanket>(
+                        &'life0 self
+                    ) -> std::pin::Pin<
+                        std::boxed::Box<dyn std::future::Future<Output = u32> + 'blanket>
+                    >
+                    where
+                        'life0: 'blanket,
+                        Self: 'blanket
+                    {
+                        std::boxed::Box::pin(async move { self.0 })
+                    }
+                }
+            )
+        );
+    }
+}
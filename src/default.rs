@@ -1,30 +1,599 @@
+use proc_macro2::{Delimiter, Spacing, TokenTree};
+use quote::ToTokens;
+use syn::ext::IdentExt;
+use syn::parse::{Parse, ParseStream};
 use syn::spanned::Spanned;
+use syn::visit::Visit;
+use syn::visit_mut::VisitMut;
+use syn::{parse_quote, parse_quote_spanned};
 
-use super::utils::{prepend_function_path, signature_to_function_call};
+use super::method::MethodArgs;
+use super::utils::{
+    fresh_type_ident, prepend_function_path, signature_to_function_call, to_snake_case,
+    trait_to_generic_ident, SelfReplacer,
+};
+
+/// The module providing the default implementations of the trait methods.
+pub struct DefaultModule {
+    /// The path to the module, inferred from the trait name if not given.
+    pub module: Option<syn::Path>,
+    /// A prefix added to the method names to get the function names.
+    pub prefix: String,
+    /// Whether methods with a provided body are kept instead of rejected.
+    pub partial: bool,
+}
+
+impl DefaultModule {
+    /// Create a default module using the method names as function names.
+    pub fn new(module: Option<syn::Path>) -> Self {
+        Self {
+            module,
+            prefix: String::new(),
+            partial: false,
+        }
+    }
+
+    /// Get the path to the module, using the `snake_case` name of the trait
+    /// if no path was given.
+    fn module(&self, trait_: &syn::Ident) -> syn::Path {
+        match self.module {
+            Some(ref module) => module.clone(),
+            None => to_snake_case(trait_).into(),
+        }
+    }
+
+    /// Build the call to the function implementing the given method.
+    fn function_call(
+        &self,
+        trait_: &syn::Ident,
+        sig: &syn::Signature,
+    ) -> syn::Result<syn::ExprCall> {
+        let mut call = signature_to_function_call(sig)?;
+        if let syn::Expr::Path(ref mut path) = *call.func {
+            if let Some(segment) = path.path.segments.last_mut() {
+                let name = format!("{}{}", self.prefix, segment.ident.unraw());
+                segment.ident = syn::Ident::new(&name, segment.ident.span());
+            }
+        }
+        prepend_function_path(&mut call, self.module(trait_))?;
+        Ok(call)
+    }
+}
+
+/// Parse the arguments of `default(module = ..., prefix = "...", partial)`.
+impl Parse for DefaultModule {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut module: Option<syn::Path> = None;
+        let mut prefix: Option<String> = None;
+        let mut partial = false;
+        while !input.is_empty() {
+            let key = input.parse::<syn::Ident>()?;
+            match key.to_string().as_str() {
+                "partial" => {
+                    if partial {
+                        return Err(syn::Error::new(key.span(), "duplicate partial given"));
+                    }
+                    partial = true;
+                }
+                "module" => {
+                    input.parse::<syn::Token![=]>()?;
+                    if module.replace(input.parse()?).is_some() {
+                        return Err(syn::Error::new(key.span(), "duplicate module given"));
+                    }
+                }
+                "prefix" => {
+                    input.parse::<syn::Token![=]>()?;
+                    let lit = input.parse::<syn::LitStr>()?;
+                    // the prefix must give a valid identifier once prepended
+                    // to the method names
+                    if syn::parse_str::<syn::Ident>(&format!("{}f", lit.value())).is_err() {
+                        return Err(syn::Error::new(lit.span(), "expected identifier prefix"));
+                    }
+                    if prefix.replace(lit.value()).is_some() {
+                        return Err(syn::Error::new(key.span(), "duplicate prefix given"));
+                    }
+                }
+                _ => {
+                    let msg = "unexpected default option, expected `module`, `prefix` or `partial`";
+                    return Err(syn::Error::new(key.span(), msg));
+                }
+            }
+            if !input.is_empty() {
+                input.parse::<syn::Token![,]>()?;
+            }
+        }
+        Ok(Self {
+            module,
+            prefix: prefix.unwrap_or_default(),
+            partial,
+        })
+    }
+}
+
+/// Render tokens the way they would be written in source code.
+///
+/// Tokens are separated by spaces, except inside paths and generics, before
+/// commas and colons, and after prefixes such as `&` or `?`. The braces are
+/// escaped, so that the result can be used in an `on_unimplemented` message.
+fn tokens_to_string<T: ToTokens>(tokens: &T) -> String {
+    let mut printer = Printer::default();
+    printer.print(tokens.to_token_stream());
+    printer.out.replace('{', "{{").replace('}', "}}")
+}
+
+/// The kind of the last token written by a [`Printer`].
+#[derive(Clone, Copy, Default, PartialEq)]
+enum Last {
+    /// Nothing, or an opening delimiter.
+    #[default]
+    Open,
+    /// A keyword, always followed by a space.
+    Keyword,
+    /// An identifier or a literal.
+    Word,
+    /// A punctuation written next to the following token, such as `&`.
+    Prefix,
+    /// The closing angle bracket of generic arguments.
+    Generics,
+    /// Any other punctuation, or a closing delimiter.
+    Punct,
+}
+
+/// A printer of types and signatures on a single line.
+#[derive(Default)]
+struct Printer {
+    out: String,
+    last: Last,
+    last_char: Option<char>,
+}
+
+impl Printer {
+    fn print(&mut self, tokens: proc_macro2::TokenStream) {
+        for token in tokens {
+            let space = match (self.last, &token) {
+                (Last::Open, _) | (Last::Prefix, _) => false,
+                (_, TokenTree::Punct(p)) if matches!(p.as_char(), ',' | ';' | '>') => false,
+                (_, TokenTree::Punct(p)) if p.as_char() == ':' => {
+                    p.spacing() == Spacing::Joint
+                        && matches!(self.last, Last::Keyword | Last::Punct)
+                }
+                (Last::Word | Last::Generics, TokenTree::Punct(p)) => p.as_char() != '<',
+                (Last::Word | Last::Generics, TokenTree::Group(g)) => {
+                    !matches!(g.delimiter(), Delimiter::Parenthesis | Delimiter::Bracket)
+                }
+                _ => true,
+            };
+            if space {
+                self.out.push(' ');
+            }
+            let last_char = self.last_char.take();
+            match token {
+                TokenTree::Group(g) => {
+                    let (open, close) = match g.delimiter() {
+                        Delimiter::Parenthesis => ("(", ")"),
+                        Delimiter::Bracket => ("[", "]"),
+                        Delimiter::Brace => ("{ ", " }"),
+                        Delimiter::None => {
+                            self.print(g.stream());
+                            continue;
+                        }
+                    };
+                    self.out.push_str(open);
+                    self.last = Last::Open;
+                    self.print(g.stream());
+                    self.out.push_str(close);
+                    self.last = Last::Punct;
+                }
+                TokenTree::Ident(i) => {
+                    let keyword = matches!(
+                        i.to_string().as_str(),
+                        "as" | "const" | "dyn" | "impl" | "mut" | "where"
+                    );
+                    self.out.push_str(&i.to_string());
+                    self.last = if keyword { Last::Keyword } else { Last::Word };
+                }
+                TokenTree::Literal(l) => {
+                    self.out.push_str(&l.to_string());
+                    self.last = Last::Word;
+                }
+                TokenTree::Punct(p) => {
+                    let c = p.as_char();
+                    self.out.push(c);
+                    // the second colon of a path separator is written next
+                    // to the following segment, unlike a single colon, and
+                    // the end of an arrow is not the end of generics
+                    let path = c == ':' && last_char == Some(':');
+                    let arrow = c == '>' && last_char == Some('-');
+                    let prefix = matches!(c, '&' | '?' | '*' | '<' | '!');
+                    self.last = if p.spacing() == Spacing::Joint || path || prefix {
+                        Last::Prefix
+                    } else if c == '>' && !arrow {
+                        Last::Generics
+                    } else {
+                        Last::Punct
+                    };
+                    self.last_char = Some(c);
+                }
+            }
+        }
+    }
+}
+
+/// Build an assertion that the default function of method `m` matches it.
+///
+/// The signature of the function is extracted from its `Fn` implementation,
+/// and compared to the signature of the method, so that a mismatch is
+/// reported on the trait method with the expected signature. The expected
+/// signature is taken as an argument, to get the implied bounds of its
+/// lifetimes. Methods whose signature cannot be spelled as a `Fn` bound,
+/// such as `async` methods or methods taking `impl Trait` arguments, are
+/// not checked.
+fn assert_signature(
+    trait_: &syn::ItemTrait,
+    m: &syn::TraitItemFn,
+    function: &syn::Expr,
+) -> Option<syn::Item> {
+    if m.sig.asyncness.is_some() || m.sig.variadic.is_some() {
+        return None;
+    }
+    let mut finder = ImplTraitFinder(false);
+    finder.visit_signature(&m.sig);
+    if finder.0 {
+        return None;
+    }
+
+    // the expected signature uses a generic type instead of `Self`, as it
+    // would be written for the default function
+    let mut sig = m.sig.clone();
+    let generic_type = trait_to_generic_ident(trait_);
+    SelfReplacer {
+        generic_type: &generic_type,
+    }
+    .visit_signature_mut(&mut sig);
+    let inputs = sig
+        .inputs
+        .iter()
+        .map(|arg| match arg {
+            syn::FnArg::Receiver(r) => (*r.ty).clone(),
+            syn::FnArg::Typed(t) => (*t.ty).clone(),
+        })
+        .collect::<Vec<_>>();
+    let output: syn::Type = match sig.output {
+        syn::ReturnType::Default => parse_quote!(()),
+        syn::ReturnType::Type(_, ref ty) => (**ty).clone(),
+    };
+
+    // the generic type is unsized unless taken by value
+    let trait_ident = &trait_.ident;
+    let (_, trait_generics, _) = trait_.generics.split_for_impl();
+    let sized = m
+        .sig
+        .receiver()
+        .is_some_and(|r| r.reference.is_none() && r.colon_token.is_none());
+    let bound: syn::TypeParam = if sized {
+        parse_quote!(#generic_type: #trait_ident #trait_generics)
+    } else {
+        parse_quote!(#generic_type: #trait_ident #trait_generics + ?Sized)
+    };
+    let mut generics = trait_.generics.clone();
+    generics.params.push(syn::GenericParam::Type(bound));
+    generics.params.extend(sig.generics.params.iter().cloned());
+    if let Some(ref where_clause) = sig.generics.where_clause {
+        let predicates = where_clause.predicates.iter().cloned();
+        generics.make_where_clause().predicates.extend(predicates);
+    }
+    SelfReplacer {
+        generic_type: &generic_type,
+    }
+    .visit_generics_mut(&mut generics);
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+
+    // build the error message with the expected signature
+    let returned = match sig.output {
+        syn::ReturnType::Default => None,
+        syn::ReturnType::Type(..) => Some(quote::quote!(-> #output)),
+    };
+    let expected = quote::quote!(fn #impl_generics (#(#inputs),*) #returned #where_clause);
+    let message = format!(
+        "default function `{}` must have signature `{}`",
+        tokens_to_string(function),
+        tokens_to_string(&expected)
+    );
+
+    // the helpers are named so that they do not shadow the function or the
+    // types of the trait
+    let args = (0..inputs.len())
+        .map(|i| quote::format_ident!("A{}", i))
+        .collect::<Vec<_>>();
+    let span = m.sig.span();
+    let fresh = |base| syn::Ident::new(&fresh_type_ident(trait_, base).to_string(), span);
+    let expected_trait = fresh("DefaultFunction");
+    let signature = fresh("signature");
+    let check = fresh("check");
+    Some(parse_quote_spanned! {span=>
+        const _: () = {
+            #[diagnostic::on_unimplemented(message = #message, label = "expected by this method")]
+            trait #expected_trait<Signature> {}
+            impl<T> #expected_trait<T> for T {}
+            #[allow(dead_code, clippy::all)]
+            fn #signature<F: Fn(#(#args),*) -> R, #(#args,)* R>(_: F) -> (#(#args,)* R,) {
+                loop {}
+            }
+            #[allow(dead_code, clippy::all)]
+            fn #check #impl_generics (_: ::core::marker::PhantomData<(#(#inputs,)* #output,)>) #where_clause {
+                let _: &dyn #expected_trait<(#(#inputs,)* #output,)> = &#signature(#function);
+            }
+        };
+    })
+}
+
+/// Check whether a signature contains an `impl Trait` type.
+struct ImplTraitFinder(bool);
+
+impl<'ast> Visit<'ast> for ImplTraitFinder {
+    fn visit_type_impl_trait(&mut self, _: &'ast syn::TypeImplTrait) {
+        self.0 = true;
+    }
+}
 
 /// Update the method declarations of `trait_` to use default implementation from `default` module.
+///
+/// Methods given a `default_fn` argument use that function instead of the
+/// function from the `default` module, and methods given a `no_default`
+/// argument are left unchanged. Methods with a provided body are rejected,
+/// unless the `default` module is `partial`, in which case they are kept.
+///
+/// Along with the updated trait, the names of the deferred methods are
+/// returned, so that the signatures of their functions can be asserted.
 pub fn defer_trait_methods(
     mut trait_: syn::ItemTrait,
-    default: syn::Path,
-) -> syn::Result<syn::ItemTrait> {
+    default: Option<&DefaultModule>,
+    method_args: &[(syn::Ident, MethodArgs)],
+) -> syn::Result<(syn::ItemTrait, Vec<syn::Ident>)> {
+    let original = trait_.clone();
+    let mut deferred = Vec::new();
     for item in trait_.items.iter_mut() {
         if let syn::TraitItem::Fn(ref mut m) = item {
+            let args = method_args
+                .iter()
+                .find(|(ident, _)| *ident == m.sig.ident)
+                .map(|(_, args)| args);
+            if args.is_some_and(|args| args.no_default) {
+                continue;
+            }
+            // get the function implementing the method, if any
+            let call = match args.and_then(|args| args.default_fn.as_ref()) {
+                Some(path) => {
+                    let mut call = signature_to_function_call(&m.sig)?;
+                    call.func = Box::new(parse_quote!(#path));
+                    call
+                }
+                None => match default {
+                    Some(default) if default.partial && m.default.is_some() => continue,
+                    Some(default) => default.function_call(&original.ident, &m.sig)?,
+                    None => continue,
+                },
+            };
             // check no default implementation was provided for the current
             // trait method
             if m.default.is_some() {
                 let msg = "method should not have default implementation if using #[blanket(default = \"...\")]";
                 return Err(syn::Error::new(item.span(), msg));
             }
+            deferred.push(m.sig.ident.clone());
             // update the declaration to include a default implementation
-            // deferring the method call to a function call in the `default_mod`
-            // module
-            let mut call = signature_to_function_call(&m.sig)?;
-            prepend_function_path(&mut call, default.clone())?;
+            // deferring the method call to the function call
             m.default = Some(syn::Block {
                 brace_token: syn::token::Brace::default(),
                 stmts: vec![syn::Stmt::Expr(syn::Expr::Call(call), None)],
             });
+            m.semi_token = None;
         }
     }
-    Ok(trait_)
+    Ok((trait_, deferred))
+}
+
+/// Assert the signatures of the default functions of the deferred methods.
+///
+/// One assertion is returned for each deferred method whose signature can
+/// be checked, to be emitted next to the trait.
+pub fn assert_signatures(trait_: &syn::ItemTrait, deferred: &[syn::Ident]) -> Vec<syn::Item> {
+    trait_
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            syn::TraitItem::Fn(m) if deferred.contains(&m.sig.ident) => {
+                match m.default.as_ref().and_then(|b| b.stmts.first()) {
+                    Some(syn::Stmt::Expr(syn::Expr::Call(call), None)) => {
+                        assert_signature(trait_, m, &call.func)
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use syn::parse_quote;
+
+    use super::DefaultModule;
+    use crate::method::MethodArgs;
+
+    fn default_fn(ident: syn::Ident, path: syn::Path) -> (syn::Ident, MethodArgs) {
+        let args = MethodArgs {
+            default_fn: Some(path),
+            ..Default::default()
+        };
+        (ident, args)
+    }
+
+    #[test]
+    fn defer_trait_methods_prefix() {
+        let trait_ = parse_quote!(
+            trait Visitor {
+                fn visit_str(&mut self, s: &str);
+                fn visit_char(&mut self, c: char);
+            }
+        );
+        let default: DefaultModule = parse_quote!(module = crate::visit, prefix = "default_");
+        let args = [default_fn(
+            parse_quote!(visit_char),
+            parse_quote!(chars::visit),
+        )];
+        assert_eq!(
+            super::defer_trait_methods(trait_, Some(&default), &args)
+                .unwrap()
+                .0,
+            parse_quote!(
+                trait Visitor {
+                    fn visit_str(&mut self, s: &str) {
+                        crate::visit::default_visit_str(self, s)
+                    }
+                    fn visit_char(&mut self, c: char) {
+                        chars::visit(self, c)
+                    }
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn defer_trait_methods_functions_only() {
+        let trait_ = parse_quote!(
+            trait Visitor {
+                fn visit_str(&mut self, s: &str) {}
+                fn visit_char(&mut self, c: char);
+            }
+        );
+        let args = [default_fn(
+            parse_quote!(visit_char),
+            parse_quote!(chars::visit),
+        )];
+        assert_eq!(
+            super::defer_trait_methods(trait_, None, &args).unwrap().0,
+            parse_quote!(
+                trait Visitor {
+                    fn visit_str(&mut self, s: &str) {}
+                    fn visit_char(&mut self, c: char) {
+                        chars::visit(self, c)
+                    }
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn defer_trait_methods_partial() {
+        let trait_ = parse_quote!(
+            trait Visitor {
+                fn visit_str(&mut self, s: &str);
+                fn visit_char(&mut self, c: char) {}
+                fn visit_end(&mut self);
+            }
+        );
+        let default: DefaultModule = parse_quote!(module = visit, partial);
+        let args = [(
+            parse_quote!(visit_end),
+            MethodArgs {
+                no_default: true,
+                ..Default::default()
+            },
+        )];
+        assert_eq!(
+            super::defer_trait_methods(trait_, Some(&default), &args)
+                .unwrap()
+                .0,
+            parse_quote!(
+                trait Visitor {
+                    fn visit_str(&mut self, s: &str) {
+                        visit::visit_str(self, s)
+                    }
+                    fn visit_char(&mut self, c: char) {}
+                    fn visit_end(&mut self);
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn defer_trait_methods_strict() {
+        let trait_ = parse_quote!(
+            trait Visitor {
+                fn visit_str(&mut self, s: &str);
+                fn visit_char(&mut self, c: char) {}
+            }
+        );
+        let default: DefaultModule = parse_quote!(module = visit);
+        assert!(super::defer_trait_methods(trait_, Some(&default), &[]).is_err());
+    }
+
+    #[test]
+    fn defer_trait_methods_inferred() {
+        let trait_ = parse_quote!(
+            trait FoldMut {
+                fn fold_expr(&mut self, e: Expr) -> Expr;
+            }
+        );
+        let default: DefaultModule = parse_quote!(prefix = "default_");
+        assert!(default.module.is_none());
+        assert_eq!(
+            super::defer_trait_methods(trait_, Some(&default), &[])
+                .unwrap()
+                .0,
+            parse_quote!(
+                trait FoldMut {
+                    fn fold_expr(&mut self, e: Expr) -> Expr {
+                        fold_mut::default_fold_expr(self, e)
+                    }
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn assert_signatures() {
+        let trait_ = parse_quote!(
+            trait Visitor<T> {
+                fn visit(&mut self, t: &T) -> Option<Self::Output>;
+                fn visit_all(&mut self, items: impl Iterator<Item = T>);
+                async fn visit_async(&mut self);
+            }
+        );
+        let default: DefaultModule = parse_quote!(module = visitor);
+        let (trait_, deferred) = super::defer_trait_methods(trait_, Some(&default), &[]).unwrap();
+        assert_eq!(deferred.len(), 3);
+        let assertions = super::assert_signatures(&trait_, &deferred);
+        assert_eq!(assertions.len(), 1);
+
+        let message = "default function `visitor::visit` must have signature \
+                       `fn<T, V: Visitor<T> + ?Sized>(&mut V, &T) -> Option<V::Output>`";
+        let tokens = quote::quote!(#(#assertions)*).to_string();
+        assert!(tokens.contains(&format!("{:?}", message)), "{}", tokens);
+    }
+
+    #[test]
+    fn tokens_to_string() {
+        let ty: syn::Type =
+            parse_quote!(&'a mut dyn Fn(<T as Iterator>::Item, [u8; 4]) -> Vec<Box<T>>);
+        assert_eq!(
+            super::tokens_to_string(&ty),
+            "&'a mut dyn Fn(<T as Iterator>::Item, [u8; 4]) -> Vec<Box<T>>"
+        );
+        let path: syn::Path = parse_quote!(::visitor::visit_char);
+        assert_eq!(super::tokens_to_string(&path), "::visitor::visit_char");
+    }
+
+    #[test]
+    fn parse_default_module() {
+        let default: DefaultModule = parse_quote!(module = visit);
+        assert_eq!(default.module, Some(parse_quote!(visit)));
+        assert_eq!(default.prefix, "");
+        assert!(!default.partial);
+        assert!(syn::parse_str::<DefaultModule>("module = visit, prefix = \"1\"").is_err());
+        assert!(syn::parse_str::<DefaultModule>("module = visit, suffix = \"_\"").is_err());
+    }
 }
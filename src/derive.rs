@@ -1,13 +1,215 @@
+use syn::ext::IdentExt;
+use syn::parse::Parse;
+use syn::parse::ParseStream;
 use syn::parse_quote;
 use syn::punctuated::Punctuated;
 use syn::spanned::Spanned;
 
+use crate::fanout::Combine;
 use crate::utils::deref_expr;
+use crate::utils::gat_where_clause;
 use crate::utils::generics_declaration_to_generics;
+use crate::utils::rewrite_precise_captures;
 use crate::utils::signature_to_associated_function_call;
+use crate::utils::signature_to_function_call;
 use crate::utils::signature_to_method_call;
 use crate::utils::trait_to_generic_ident;
 
+/// The attributes of the trait methods forwarded to the derived methods.
+///
+/// `#[deprecated]` is not forwarded as such, since it has no effect on trait
+/// implementations, but silences the deprecation warning in the forwarding
+/// method instead. `#[must_use]` is not forwarded either, since it has no
+/// effect on trait implementations and already applies through the trait
+/// declaration.
+pub const FORWARDED_ATTRIBUTES: &[&str] = &[
+    "cfg",
+    "cfg_attr",
+    "allow",
+    "expect",
+    "warn",
+    "deny",
+    "forbid",
+    "deprecated",
+    "track_caller",
+];
+
+/// The inline attribute added to the derived methods.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Inline {
+    Always,
+    #[default]
+    Hint,
+    Never,
+    None,
+}
+
+impl Inline {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "always" => Some(Inline::Always),
+            "hint" => Some(Inline::Hint),
+            "never" => Some(Inline::Never),
+            "none" => Some(Inline::None),
+            _ => None,
+        }
+    }
+
+    /// Get the attribute to add to the derived methods, if any.
+    pub fn attribute(&self) -> Option<syn::Attribute> {
+        match self {
+            Inline::Always => Some(parse_quote!(#[inline(always)])),
+            Inline::Hint => Some(parse_quote!(#[inline])),
+            Inline::Never => Some(parse_quote!(#[inline(never)])),
+            Inline::None => None,
+        }
+    }
+}
+
+/// Options for the derived implementations.
+#[derive(Clone, Default)]
+pub struct Options {
+    /// The name of the generic type, defaults to the trait initials.
+    pub generic_type: Option<syn::Ident>,
+    /// The attributes forwarded to the derived methods, defaults to
+    /// [`FORWARDED_ATTRIBUTES`].
+    pub forward_attrs: Option<Vec<syn::Ident>>,
+    /// The configuration predicate required for the implementation.
+    pub cfg: Option<syn::Meta>,
+    /// Additional predicates for the where clause of the implementation.
+    pub predicates: Vec<syn::WherePredicate>,
+    /// The inline attribute added to the derived methods.
+    pub inline: Inline,
+    /// Functions called instead of forwarding the given methods.
+    pub overrides: Vec<(syn::Ident, syn::Path)>,
+    /// The strategies combining the results of the given methods, for the
+    /// wrapper types calling several values.
+    pub combine: Vec<(syn::Ident, Combine)>,
+    /// The maximum arity of the tuples, for the `Tuple` derive.
+    pub max: Option<usize>,
+    /// The values returned by the given methods when called on `None`, for
+    /// the `Option` derive.
+    pub on_none: Vec<(syn::Ident, syn::Expr)>,
+}
+
+/// Parse the options of a single derive, such as `Box(inline = never)`.
+impl Parse for Options {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut options = Options::default();
+        let mut cfg = false;
+        let mut predicates = false;
+        let mut inline = false;
+        let mut max = false;
+
+        while !input.is_empty() {
+            let key = input.call(syn::Ident::parse_any)?;
+            input.parse::<syn::Token![=]>()?;
+            match key.to_string().as_str() {
+                "cfg" if !cfg => {
+                    options.cfg = Some(input.parse()?);
+                    cfg = true;
+                }
+                "where" if !predicates => {
+                    // predicates are separated by commas, like other options,
+                    // but cannot start with `key =`
+                    loop {
+                        options.predicates.push(input.parse()?);
+                        let fork = input.fork();
+                        if fork.parse::<syn::Token![,]>().is_err()
+                            || fork.is_empty()
+                            || (fork.peek(syn::Ident::peek_any) && fork.peek2(syn::Token![=]))
+                        {
+                            break;
+                        }
+                        input.parse::<syn::Token![,]>()?;
+                    }
+                    predicates = true;
+                }
+                "inline" if !inline => {
+                    let value = input.parse::<syn::Ident>()?;
+                    options.inline = Inline::from_str(&value.to_string()).ok_or_else(|| {
+                        syn::Error::new(
+                            value.span(),
+                            "expected one of `always`, `hint`, `never` or `none`",
+                        )
+                    })?;
+                    inline = true;
+                }
+                "max" if !max => {
+                    let value = input.parse::<syn::LitInt>()?;
+                    match value.base10_parse::<usize>()? {
+                        0 => {
+                            return Err(syn::Error::new(value.span(), "expected a positive arity"))
+                        }
+                        arity => options.max = Some(arity),
+                    }
+                    max = true;
+                }
+                "cfg" | "where" | "inline" | "max" => {
+                    let msg = format!("duplicate {} given", key);
+                    return Err(syn::Error::new(key.span(), msg));
+                }
+                _ => {
+                    let msg =
+                        "unexpected derive option, expected `cfg`, `where`, `inline` or `max`";
+                    return Err(syn::Error::new(key.span(), msg));
+                }
+            }
+            if !input.is_empty() {
+                input.parse::<syn::Token![,]>()?;
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+impl Options {
+    /// Get the generic type used in the implementation of the given trait.
+    pub fn generic_type(&self, trait_: &syn::ItemTrait) -> syn::Ident {
+        self.generic_type
+            .clone()
+            .unwrap_or_else(|| trait_to_generic_ident(trait_))
+    }
+
+    /// Get the strategy combining the results of the given method, if any.
+    pub fn combine(&self, method: &syn::Ident) -> Option<Combine> {
+        self.combine
+            .iter()
+            .find(|(ident, _)| ident == method)
+            .map(|(_, combine)| *combine)
+    }
+
+    /// Get the value returned by the given method when called on `None`, if any.
+    pub fn on_none(&self, method: &syn::Ident) -> Option<&syn::Expr> {
+        self.on_none
+            .iter()
+            .find(|(ident, _)| ident == method)
+            .map(|(_, expr)| expr)
+    }
+
+    /// Get the attributes of a trait method to add to a derived method.
+    pub fn forwarded_attrs(&self, attrs: &[syn::Attribute]) -> Vec<syn::Attribute> {
+        let forwarded = |name: &str| match self.forward_attrs {
+            Some(ref idents) => idents.iter().any(|i| i == name),
+            None => FORWARDED_ATTRIBUTES.contains(&name),
+        };
+        attrs
+            .iter()
+            .filter_map(|attr| {
+                let ident = attr.path().get_ident()?.to_string();
+                if !forwarded(&ident) {
+                    None
+                } else if ident == "deprecated" {
+                    Some(parse_quote!(#[allow(deprecated)]))
+                } else {
+                    Some(attr.clone())
+                }
+            })
+            .collect()
+    }
+}
+
 /// The different receivers supported on a method.
 #[derive(Debug, PartialEq)]
 pub enum Receiver {
@@ -28,9 +230,17 @@ pub trait WrapperType {
     /// Additional types to add to the generic type bound.
     const BOUNDS: &'static [&'static str] = &[];
 
+    /// Whether the wrapped type must be `Sized`, such as collection elements.
+    const SIZED: bool = false;
+
     /// Wrap the given identifier into the wrapper type.
     fn wrap(ty: &syn::Ident) -> syn::Type;
 
+    /// Additional generic parameters used by the wrapper type.
+    fn extra_generics() -> Vec<syn::GenericParam> {
+        Vec::new()
+    }
+
     /// Check that the given receiver is supported for the wrapper type.
     fn check_receiver(r: &syn::Receiver) -> syn::Result<()> {
         let receivers = Self::RECEIVERS;
@@ -59,11 +269,22 @@ pub trait WrapperType {
         }
     }
 
+    /// Check that all the receivers of the given trait are supported.
+    fn supports(trait_: &syn::ItemTrait) -> bool {
+        trait_.items.iter().all(|item| match item {
+            syn::TraitItem::Fn(m) => m
+                .sig
+                .receiver()
+                .is_none_or(|r| Self::check_receiver(r).is_ok()),
+            _ => true,
+        })
+    }
+
     /// Generate the derived implementation for the given trait.
-    fn derive(trait_: &syn::ItemTrait) -> syn::Result<syn::ItemImpl> {
+    fn derive(trait_: &syn::ItemTrait, options: &Options) -> syn::Result<syn::ItemImpl> {
         // build an identifier for the generic type used for the implementation
         let trait_ident = &trait_.ident;
-        let generic_type = trait_to_generic_ident(trait_);
+        let generic_type = options.generic_type(trait_);
         let wrapper_type = Self::wrap(&generic_type);
 
         // build the generics for the impl block:
@@ -81,14 +302,23 @@ pub trait WrapperType {
         // build the methods
         let mut methods: Vec<syn::ImplItemFn> = Vec::new();
         let mut assoc_types: Vec<syn::ImplItemType> = Vec::new();
+        let mut errors: Option<syn::Error> = None;
         for item in trait_.items.iter() {
             if let syn::TraitItem::Fn(ref m) = item {
-                methods.push(Self::derive_method(
+                // report all unsupported methods at once
+                match Self::derive_method(
                     m,
                     trait_ident,
                     &generic_type,
                     &trait_generic_names,
-                )?)
+                    options,
+                ) {
+                    Ok(method) => methods.push(method),
+                    Err(e) => match errors {
+                        Some(ref mut err) => err.combine(e),
+                        None => errors = Some(e),
+                    },
+                }
             }
 
             if let syn::TraitItem::Type(t) = item {
@@ -96,7 +326,8 @@ pub trait WrapperType {
                 let attrs = &t.attrs;
 
                 let t_generics = &t.generics;
-                let where_clause = &t.generics.where_clause;
+                let where_clause =
+                    gat_where_clause(t.generics.where_clause.as_ref(), &generic_type);
                 let mut t_generic_names = t_generics.clone();
                 t_generic_names.params = generics_declaration_to_generics(&t_generics.params)?;
 
@@ -105,6 +336,10 @@ pub trait WrapperType {
             }
         }
 
+        if let Some(err) = errors {
+            return Err(err);
+        }
+
         // check if any method has a `Self` receiver, which would mean we cannot
         // relax the `Sized` trait requirement
         let mut sized = false;
@@ -119,7 +354,7 @@ pub trait WrapperType {
         // Add generic type for the type we are creating ourselves
         let span = generic_type.span();
         let mut bounds: Punctuated<_, _> = parse_quote!(#trait_ident #trait_generic_names);
-        if !sized {
+        if !sized && !Self::SIZED {
             bounds.push(parse_quote!(?Sized));
         }
         for bound in Self::BOUNDS {
@@ -138,8 +373,20 @@ pub trait WrapperType {
                 eq_token: None,
                 default: None,
             }));
+        impl_generics.params.extend(Self::extra_generics());
+
+        // Add the additional predicates and configuration of the derive
+        let mut where_clause = where_clause.clone();
+        if !options.predicates.is_empty() {
+            where_clause
+                .get_or_insert_with(|| parse_quote!(where))
+                .predicates
+                .extend(options.predicates.iter().cloned());
+        }
+        let cfg = options.cfg.iter();
 
         Ok(parse_quote!(
+            #(#[cfg(#cfg)])*
             #[automatically_derived]
             impl #impl_generics #trait_ident #trait_generic_names for #wrapper_type #where_clause {
                 #(#assoc_types)*
@@ -154,8 +401,45 @@ pub trait WrapperType {
         trait_ident: &syn::Ident,
         generic_type: &syn::Ident,
         trait_generic_names: &syn::Generics,
+        options: &Options,
     ) -> syn::Result<syn::ImplItemFn> {
-        let mut call: syn::Expr = if let Some(r) = m.sig.receiver() {
+        let override_ = options
+            .overrides
+            .iter()
+            .find(|(ident, _)| *ident == m.sig.ident);
+        let block: syn::Block = if let Some((_, path)) = override_ {
+            // the function is given the wrapper as the receiver, so it can
+            // implement methods with receivers the wrapper cannot forward
+            let mut call = signature_to_function_call(&m.sig)?;
+            call.func = Box::new(parse_quote!(#path));
+            let call = await_expr(call.into(), &m.sig);
+            parse_quote!({ #call })
+        } else {
+            Self::forward_method(m, trait_ident, generic_type, trait_generic_names, options)?
+        };
+
+        // `Self` cannot appear in the `use<..>` bounds of the impl, and the
+        // wrapper may capture less than the trait allows, which is fine since
+        // the forwarded opaque type is the one of the wrapped type
+        let mut signature = m.sig.clone();
+        let mut attrs = options.forwarded_attrs(&m.attrs);
+        attrs.extend(options.inline.attribute());
+        if rewrite_precise_captures(&mut signature, &[generic_type]) {
+            Ok(syn::parse_quote!(#(#attrs)* #[allow(refining_impl_trait)] #signature #block))
+        } else {
+            Ok(syn::parse_quote!(#(#attrs)* #signature #block))
+        }
+    }
+
+    /// Generate the body of a derived method forwarding to the wrapped type.
+    fn forward_method(
+        m: &syn::TraitItemFn,
+        trait_ident: &syn::Ident,
+        generic_type: &syn::Ident,
+        trait_generic_names: &syn::Generics,
+        _options: &Options,
+    ) -> syn::Result<syn::Block> {
+        let call: syn::Expr = if let Some(r) = m.sig.receiver() {
             Self::check_receiver(r)?;
             let mut call = signature_to_method_call(&m.sig)?;
             if r.reference.is_some() {
@@ -173,19 +457,24 @@ pub trait WrapperType {
             )?;
             call.into()
         };
+        let call = await_expr(call, &m.sig);
+        Ok(parse_quote!({ #call }))
+    }
+}
 
-        if let Some(async_) = m.sig.asyncness {
+/// Await the given call if the method signature is `async`.
+pub fn await_expr(call: syn::Expr, sig: &syn::Signature) -> syn::Expr {
+    match sig.asyncness {
+        Some(async_) => {
             let span = async_.span();
-            call = syn::ExprAwait {
+            syn::ExprAwait {
                 attrs: Vec::new(),
                 base: Box::new(call),
                 dot_token: syn::Token![.](span),
                 await_token: syn::Token![await](span),
             }
-            .into();
+            .into()
         }
-
-        let signature = &m.sig;
-        Ok(syn::parse_quote!(#[inline] #signature { #call }))
+        None => call,
     }
 }
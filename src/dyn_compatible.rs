@@ -0,0 +1,949 @@
+use std::collections::HashMap;
+
+use syn::parse_quote;
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::visit::Visit;
+use syn::visit_mut::VisitMut;
+
+use crate::boxed::box_signature_future;
+use crate::boxed::elaborate_signature_lifetimes;
+use crate::derive::Options;
+use crate::utils::generics_declaration_to_generics;
+use crate::utils::signature_to_associated_function_call;
+
+/// Traits implemented by `Box<dyn Trait>` in the standard library.
+///
+/// A method returning `impl Trait` with only these bounds can be implemented
+/// for the trait object by returning the boxed value of the companion trait.
+const BOXED_TRAITS: &[&str] = &[
+    "Debug",
+    "DoubleEndedIterator",
+    "Display",
+    "Error",
+    "ExactSizeIterator",
+    "Fn",
+    "FnMut",
+    "FnOnce",
+    "Future",
+    "Iterator",
+    "Send",
+    "Sync",
+    "Unpin",
+];
+
+/// Auto traits, which can be added to any trait object.
+const AUTO_TRAITS: &[&str] = &["Send", "Sync", "Unpin", "UnwindSafe", "RefUnwindSafe"];
+
+/// Count the trait bounds which are not auto traits.
+///
+/// Trait objects can only be built from a single trait besides auto traits
+/// and lifetimes.
+fn count_non_auto_traits(bounds: &Punctuated<syn::TypeParamBound, syn::Token![+]>) -> usize {
+    bounds
+        .iter()
+        .filter(|b| match b {
+            syn::TypeParamBound::Trait(t) => t
+                .path
+                .segments
+                .last()
+                .is_none_or(|s| !AUTO_TRAITS.contains(&s.ident.to_string().as_str())),
+            _ => false,
+        })
+        .count()
+}
+
+/// Count the occurrences of the given identifiers used as a type.
+struct TypeCounter<'a> {
+    idents: &'a [&'a syn::Ident],
+    count: usize,
+    references: usize,
+}
+
+impl<'ast> Visit<'ast> for TypeCounter<'_> {
+    fn visit_type_path(&mut self, ty: &'ast syn::TypePath) {
+        if ty.qself.is_none() && self.idents.iter().any(|i| ty.path.is_ident(*i)) {
+            self.count += 1;
+        }
+        syn::visit::visit_type_path(self, ty);
+    }
+
+    fn visit_type_reference(&mut self, ty: &'ast syn::TypeReference) {
+        if let syn::Type::Path(ref p) = *ty.elem {
+            if p.qself.is_none() && self.idents.iter().any(|i| p.path.is_ident(*i)) {
+                self.references += 1;
+            }
+        }
+        syn::visit::visit_type_reference(self, ty);
+    }
+}
+
+/// Replace references to erased generic types with references to trait objects.
+struct TypeEraser<'a> {
+    erased: &'a HashMap<syn::Ident, syn::Type>,
+}
+
+impl VisitMut for TypeEraser<'_> {
+    fn visit_type_reference_mut(&mut self, ty: &mut syn::TypeReference) {
+        if let syn::Type::Path(ref p) = *ty.elem {
+            if let Some(ident) = p.path.get_ident() {
+                if let Some(object) = self.erased.get(ident) {
+                    *ty.elem = object.clone();
+                    return;
+                }
+            }
+        }
+        syn::visit_mut::visit_type_reference_mut(self, ty);
+    }
+}
+
+/// Check whether any of the given types contains an `impl Trait` type.
+fn contains_impl_trait<'a>(types: impl IntoIterator<Item = &'a syn::Type>) -> bool {
+    struct Finder(bool);
+    impl<'ast> Visit<'ast> for Finder {
+        fn visit_type_impl_trait(&mut self, _: &'ast syn::TypeImplTrait) {
+            self.0 = true;
+        }
+    }
+    let mut finder = Finder(false);
+    for ty in types {
+        finder.visit_type(ty);
+    }
+    finder.0
+}
+
+/// Get the trait bounds of a return type if it is an `impl Trait` type.
+fn impl_trait_bounds(
+    output: &syn::ReturnType,
+) -> Option<Punctuated<syn::TypeParamBound, syn::Token![+]>> {
+    match output {
+        syn::ReturnType::Type(_, ty) => match **ty {
+            syn::Type::ImplTrait(ref impl_) => Some(
+                impl_
+                    .bounds
+                    .iter()
+                    .filter(|b| matches!(b, syn::TypeParamBound::Trait(_)))
+                    .cloned()
+                    .collect(),
+            ),
+            _ => None,
+        },
+        syn::ReturnType::Default => None,
+    }
+}
+
+/// Check whether a signature requires `Self: Sized` in its where clause.
+fn requires_sized(sig: &syn::Signature) -> bool {
+    let where_clause = match sig.generics.where_clause {
+        Some(ref w) => w,
+        None => return false,
+    };
+    where_clause.predicates.iter().any(|p| match p {
+        syn::WherePredicate::Type(t) => {
+            crate::utils::is_self_type(&t.bounded_ty)
+                && t.bounds.iter().any(|b| match b {
+                    syn::TypeParamBound::Trait(t) => {
+                        matches!(t.modifier, syn::TraitBoundModifier::None)
+                            && t.path.segments.last().is_some_and(|s| s.ident == "Sized")
+                    }
+                    _ => false,
+                })
+        }
+        _ => false,
+    })
+}
+
+/// Get a generic type parameter standing for an associated type of a trait.
+fn associated_type_param(t: &syn::TraitItemType, trait_generics: &syn::Generics) -> syn::TypeParam {
+    let t_ident = &t.ident;
+    let bounds = &t.bounds;
+    let mut param = syn::Ident::new(&t_ident.to_string(), t_ident.span());
+    while trait_generics.type_params().any(|p| p.ident == param) {
+        param = syn::Ident::new(&format!("{}_", param), param.span());
+    }
+    if bounds.is_empty() {
+        parse_quote!(#param)
+    } else {
+        parse_quote!(#param: #bounds)
+    }
+}
+
+/// Get the trait object type of a trait with the given associated types.
+///
+/// Given `Trait<T>` with `type Item;`, get `dyn Trait<T, Item = Item> + 'a`.
+fn trait_object_type(
+    trait_ident: &syn::Ident,
+    trait_generics: &syn::Generics,
+    assoc_types: &[(syn::Ident, syn::TypeParam)],
+    lifetime: &syn::Lifetime,
+) -> syn::Result<syn::Type> {
+    let mut args = generics_declaration_to_generics(&trait_generics.params)?
+        .into_iter()
+        .map(|p| match p {
+            syn::GenericParam::Lifetime(l) => syn::GenericArgument::Lifetime(l.lifetime),
+            syn::GenericParam::Type(t) => {
+                let ident = t.ident;
+                syn::GenericArgument::Type(parse_quote!(#ident))
+            }
+            syn::GenericParam::Const(c) => {
+                let ident = c.ident;
+                syn::GenericArgument::Const(parse_quote!(#ident))
+            }
+        })
+        .collect::<Punctuated<syn::GenericArgument, syn::Token![,]>>();
+    for (t_ident, param) in assoc_types.iter() {
+        let param = &param.ident;
+        args.push(parse_quote!(#t_ident = #param));
+    }
+    if args.is_empty() {
+        Ok(parse_quote!(dyn #trait_ident + #lifetime))
+    } else {
+        Ok(parse_quote!(dyn #trait_ident<#args> + #lifetime))
+    }
+}
+
+/// A method of the original trait, erased to be dyn-compatible.
+struct ErasedMethod {
+    /// The declaration of the method in the companion trait.
+    declaration: syn::TraitItemFn,
+    /// The body forwarding the method to the original trait.
+    forward: syn::Expr,
+    /// Whether the original method can be implemented with the erased one.
+    reversible: bool,
+}
+
+/// A companion trait of the original trait which is dyn-compatible.
+pub struct DynCompatible {
+    pub ident: syn::Ident,
+}
+
+impl DynCompatible {
+    /// Erase the signature of a method, if the method can be dispatched.
+    ///
+    /// Methods without a receiver, requiring `Self: Sized`, or mentioning
+    /// `Self` or `impl Trait` in their arguments cannot be dispatched, and
+    /// neither can generic methods, unless the generic types are `?Sized` and
+    /// only used behind references, in which case they are replaced with
+    /// trait objects. Generic types and returned `impl Trait` types bounded
+    /// by several traits besides auto traits cannot be trait objects, so
+    /// their methods cannot be dispatched either.
+    fn erase_method(
+        &self,
+        m: &syn::TraitItemFn,
+        trait_ident: &syn::Ident,
+        generic_type: &syn::Ident,
+        trait_generic_names: &syn::Generics,
+        options: &Options,
+    ) -> syn::Result<Option<ErasedMethod>> {
+        let receiver = match m.sig.receiver() {
+            Some(r) => r,
+            None => return Ok(None),
+        };
+        if requires_sized(&m.sig) || m.sig.generics.const_params().next().is_some() {
+            return Ok(None);
+        }
+
+        let inputs = m.sig.inputs.iter().filter_map(|arg| match arg {
+            syn::FnArg::Typed(t) => Some(t.ty.as_ref()),
+            syn::FnArg::Receiver(_) => None,
+        });
+        let output = match m.sig.output {
+            syn::ReturnType::Type(_, ref ty) => Some(ty.as_ref()),
+            syn::ReturnType::Default => None,
+        };
+
+        // `Self` cannot appear outside of the receiver
+        let self_ident = syn::Ident::new("Self", m.sig.span());
+        let mut counter = TypeCounter {
+            idents: &[&self_ident],
+            count: 0,
+            references: 0,
+        };
+        inputs
+            .clone()
+            .chain(output)
+            .for_each(|ty| counter.visit_type(ty));
+        if counter.count > 0 || contains_impl_trait(inputs.clone()) {
+            return Ok(None);
+        }
+
+        // generic types can only be erased if they are used behind references
+        let mut sig = m.sig.clone();
+        let mut erased = HashMap::new();
+        for param in m.sig.generics.type_params() {
+            let mut bounds = param.bounds.clone();
+            let unsized_ = bounds.iter().any(|b| match b {
+                syn::TypeParamBound::Trait(t) => {
+                    matches!(t.modifier, syn::TraitBoundModifier::Maybe(_))
+                }
+                _ => false,
+            });
+            bounds = bounds
+                .into_iter()
+                .filter(|b| match b {
+                    syn::TypeParamBound::Trait(t) => {
+                        !matches!(t.modifier, syn::TraitBoundModifier::Maybe(_))
+                    }
+                    _ => true,
+                })
+                .collect();
+            if !unsized_
+                || !bounds
+                    .iter()
+                    .any(|b| matches!(b, syn::TypeParamBound::Trait(_)))
+                || count_non_auto_traits(&bounds) > 1
+            {
+                return Ok(None);
+            }
+
+            let idents = [&param.ident];
+            let mut counter = TypeCounter {
+                idents: &idents,
+                count: 0,
+                references: 0,
+            };
+            inputs.clone().for_each(|ty| counter.visit_type(ty));
+            if counter.count != counter.references {
+                return Ok(None);
+            }
+            counter.count = 0;
+            output.into_iter().for_each(|ty| counter.visit_type(ty));
+            if let Some(ref w) = m.sig.generics.where_clause {
+                counter.visit_where_clause(w);
+            }
+            if counter.count > 0 {
+                return Ok(None);
+            }
+
+            let object: syn::Type = if bounds.len() > 1 {
+                parse_quote!((dyn #bounds))
+            } else {
+                parse_quote!(dyn #bounds)
+            };
+            erased.insert(param.ident.clone(), object);
+        }
+        if !erased.is_empty() {
+            sig.generics.params = sig
+                .generics
+                .params
+                .into_iter()
+                .filter(|p| !matches!(p, syn::GenericParam::Type(_)))
+                .collect();
+            let mut eraser = TypeEraser { erased: &erased };
+            for arg in sig.inputs.iter_mut() {
+                if let syn::FnArg::Typed(ref mut t) = arg {
+                    eraser.visit_type_mut(&mut t.ty);
+                }
+            }
+        }
+
+        // build the call to the original trait method
+        let mut call = signature_to_associated_function_call(
+            &m.sig,
+            trait_ident,
+            generic_type,
+            trait_generic_names,
+        )?;
+        let mut reversible = erased.is_empty();
+
+        // methods taking `self` by value receive a `Box<Self>` instead
+        if receiver.reference.is_none() && receiver.colon_token.is_none() {
+            let span = receiver.self_token.span;
+            if let Some(syn::FnArg::Receiver(ref mut r)) = sig.inputs.first_mut() {
+                *r = syn::parse_quote_spanned!(span=> self: std::boxed::Box<Self>);
+            }
+            if let Some(first) = call.args.first_mut() {
+                *first = parse_quote!(*self);
+            }
+            reversible = false;
+        }
+
+        // box the returned futures and `impl Trait` types
+        let forward: syn::Expr = if sig.asyncness.is_some() {
+            box_signature_future(&mut sig, &Punctuated::new());
+            parse_quote!(std::boxed::Box::pin(#call))
+        } else if let Some(bounds) = impl_trait_bounds(&m.sig.output) {
+            // like generic types, `impl Trait` types with several traits
+            // cannot be turned into trait objects
+            if count_non_auto_traits(&bounds) > 1 {
+                return Ok(None);
+            }
+            let lifetime = syn::Lifetime::new("'blanket", m.sig.output.span());
+            elaborate_signature_lifetimes(&mut sig, &lifetime);
+            let mut bounds = impl_trait_bounds(&sig.output).unwrap_or_default();
+            let names = bounds
+                .iter()
+                .filter_map(|b| match b {
+                    syn::TypeParamBound::Trait(t) => t.path.segments.last(),
+                    _ => None,
+                })
+                .map(|s| s.ident.to_string())
+                .collect::<Vec<_>>();
+            reversible &= names.iter().all(|n| BOXED_TRAITS.contains(&n.as_str()));
+            bounds.push(parse_quote!(#lifetime));
+            if names.iter().any(|n| n == "Future") {
+                sig.output = parse_quote!(-> std::pin::Pin<std::boxed::Box<dyn #bounds>>);
+                parse_quote!(std::boxed::Box::pin(#call))
+            } else {
+                sig.output = parse_quote!(-> std::boxed::Box<dyn #bounds>);
+                parse_quote!(std::boxed::Box::new(#call))
+            }
+        } else if contains_impl_trait(output) {
+            return Ok(None);
+        } else {
+            call.into()
+        };
+
+        let attrs = m
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("doc"))
+            .cloned()
+            .chain(options.forwarded_attrs(&m.attrs));
+        Ok(Some(ErasedMethod {
+            declaration: parse_quote!(#(#attrs)* #sig;),
+            forward,
+            reversible,
+        }))
+    }
+
+    /// Generate the companion trait, its blanket implementation for all
+    /// implementors of the original trait, and if possible an implementation
+    /// of the original trait for the companion trait object.
+    pub fn derive(
+        &self,
+        trait_: &syn::ItemTrait,
+        options: &Options,
+    ) -> syn::Result<Vec<syn::Item>> {
+        let trait_ident = &trait_.ident;
+        let dyn_ident = &self.ident;
+        let vis = &trait_.vis;
+        let attrs = trait_
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("doc"))
+            .collect::<Vec<_>>();
+
+        let trait_generics = &trait_.generics;
+        let where_clause = &trait_.generics.where_clause;
+        let mut trait_generic_names = trait_generics.clone();
+        trait_generic_names.params = generics_declaration_to_generics(&trait_generics.params)?;
+        let generic_type = options.generic_type(trait_);
+
+        // the original trait can only be implemented for the trait object
+        // if all of its items can be forwarded to the companion trait
+        let mut reversible = trait_.supertraits.is_empty();
+
+        let mut declarations: Vec<syn::TraitItem> = Vec::new();
+        let mut forwards: Vec<syn::ImplItem> = Vec::new();
+        let mut reverses: Vec<syn::ImplItem> = Vec::new();
+        let mut assoc_types: Vec<(syn::Ident, syn::TypeParam)> = Vec::new();
+        for item in trait_.items.iter() {
+            match item {
+                syn::TraitItem::Type(t) => {
+                    if !t.generics.params.is_empty() {
+                        let msg = "cannot generate a dyn-compatible trait for a trait with generic associated types";
+                        return Err(syn::Error::new(t.span(), msg));
+                    }
+                    let t_ident = &t.ident;
+                    declarations.push(syn::TraitItem::Type(t.clone()));
+                    forwards.push(parse_quote!(
+                        type #t_ident = <#generic_type as #trait_ident #trait_generic_names>::#t_ident;
+                    ));
+                    let param = associated_type_param(t, trait_generics);
+                    let param_ident = &param.ident;
+                    reverses.push(parse_quote!(type #t_ident = #param_ident;));
+                    assoc_types.push((t_ident.clone(), param));
+                }
+                syn::TraitItem::Const(c) => {
+                    reversible &= c.default.is_some();
+                }
+                syn::TraitItem::Fn(m) => {
+                    let erased = self.erase_method(
+                        m,
+                        trait_ident,
+                        &generic_type,
+                        &trait_generic_names,
+                        options,
+                    )?;
+                    let attrs = options.forwarded_attrs(&m.attrs);
+                    match erased {
+                        Some(e) => {
+                            let sig = &e.declaration.sig;
+                            let forward = &e.forward;
+                            forwards.push(parse_quote!(#(#attrs)* #[inline] #sig { #forward }));
+                            if e.reversible {
+                                let mut call: syn::Expr = signature_to_associated_function_call(
+                                    &m.sig,
+                                    dyn_ident,
+                                    &syn::Ident::new("Self", m.sig.span()),
+                                    &trait_generic_names,
+                                )?
+                                .into();
+                                if m.sig.asyncness.is_some() {
+                                    call = parse_quote!(#call.await);
+                                }
+                                let sig = &m.sig;
+                                reverses.push(parse_quote!(#(#attrs)* #[inline] #sig { #call }));
+                            } else {
+                                reversible &= m.default.is_some() || requires_sized(&m.sig);
+                            }
+                            declarations.push(syn::TraitItem::Fn(e.declaration));
+                        }
+                        None => {
+                            reversible &= m.default.is_some() || requires_sized(&m.sig);
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        let mut items: Vec<syn::Item> = Vec::new();
+        items.push(parse_quote!(
+            #(#attrs)*
+            #vis trait #dyn_ident #trait_generics #where_clause {
+                #(#declarations)*
+            }
+        ));
+
+        let mut impl_generics = trait_generics.clone();
+        impl_generics
+            .params
+            .push(parse_quote!(#generic_type: #trait_ident #trait_generic_names));
+        items.push(parse_quote!(
+            #[automatically_derived]
+            impl #impl_generics #dyn_ident #trait_generic_names for #generic_type #where_clause {
+                #(#forwards)*
+            }
+        ));
+
+        if reversible {
+            let lifetime = syn::Lifetime::new("'blanket", self.ident.span());
+            let mut impl_generics = trait_generics.clone();
+            impl_generics.params.insert(0, parse_quote!(#lifetime));
+            impl_generics.params.extend(
+                assoc_types
+                    .iter()
+                    .map(|(_, p)| syn::GenericParam::Type(p.clone())),
+            );
+            let dyn_type = trait_object_type(dyn_ident, trait_generics, &assoc_types, &lifetime)?;
+            items.push(parse_quote!(
+                #[automatically_derived]
+                impl #impl_generics #trait_ident #trait_generic_names for #dyn_type #where_clause {
+                    #(#reverses)*
+                }
+            ));
+        }
+
+        Ok(items)
+    }
+}
+
+/// Collect the spans of `Self` and `impl Trait` types.
+#[derive(Default)]
+struct ViolationFinder {
+    self_types: Vec<proc_macro2::Span>,
+    impl_traits: Vec<proc_macro2::Span>,
+}
+
+impl<'ast> Visit<'ast> for ViolationFinder {
+    fn visit_type_path(&mut self, ty: &'ast syn::TypePath) {
+        if ty.qself.is_none() && ty.path.is_ident("Self") {
+            self.self_types.push(ty.span());
+        }
+        syn::visit::visit_type_path(self, ty);
+    }
+
+    fn visit_type_impl_trait(&mut self, ty: &'ast syn::TypeImplTrait) {
+        self.impl_traits.push(ty.span());
+        syn::visit::visit_type_impl_trait(self, ty);
+    }
+}
+
+/// Check that a trait is dyn-compatible.
+///
+/// All the items preventing the trait from being used as a trait object
+/// are reported at once, otherwise an item asserting that the trait object
+/// type is well-formed is returned.
+pub fn assert_dyn_compatible(trait_: &syn::ItemTrait) -> syn::Result<syn::Item> {
+    let trait_ident = &trait_.ident;
+    let mut errors: Vec<syn::Error> = Vec::new();
+    let mut error = |span: proc_macro2::Span, msg: String| errors.push(syn::Error::new(span, msg));
+
+    for bound in trait_.supertraits.iter() {
+        if let syn::TypeParamBound::Trait(t) = bound {
+            if t.path.is_ident("Sized") && matches!(t.modifier, syn::TraitBoundModifier::None) {
+                error(
+                    t.span(),
+                    format!(
+                        "trait `{}` is not dyn-compatible because it requires `Self: Sized`; consider adding `where Self: Sized` to the methods that need it instead",
+                        trait_ident
+                    ),
+                );
+            }
+        }
+    }
+
+    let mut assoc_types: Vec<(syn::Ident, syn::TypeParam)> = Vec::new();
+    for item in trait_.items.iter() {
+        match item {
+            syn::TraitItem::Const(c) => error(
+                c.ident.span(),
+                format!(
+                    "associated const `{}` is not dyn-compatible; consider replacing it with a method returning its value",
+                    c.ident
+                ),
+            ),
+            syn::TraitItem::Type(t) => {
+                if !t.generics.params.is_empty() {
+                    error(
+                        t.generics.span(),
+                        format!(
+                            "generic associated type `{}` is not dyn-compatible; consider moving it to a separate trait",
+                            t.ident
+                        ),
+                    );
+                }
+                assoc_types.push((t.ident.clone(), associated_type_param(t, &trait_.generics)));
+            }
+            syn::TraitItem::Fn(m) => {
+                if requires_sized(&m.sig) {
+                    continue;
+                }
+                let ident = &m.sig.ident;
+                let suggestion = "consider adding `where Self: Sized` to the method";
+
+                // a single method can break several rules, and each of them
+                // is reported, even though they all have the same fix
+                let mut finder = ViolationFinder::default();
+                for arg in m.sig.inputs.iter() {
+                    if let syn::FnArg::Typed(ref t) = arg {
+                        finder.visit_type(&t.ty);
+                    }
+                }
+                let self_arguments = std::mem::take(&mut finder.self_types);
+                if let syn::ReturnType::Type(_, ref ty) = m.sig.output {
+                    finder.visit_type(ty);
+                }
+                let generic = m
+                    .sig
+                    .generics
+                    .params
+                    .iter()
+                    .find(|p| !matches!(p, syn::GenericParam::Lifetime(_)));
+
+                if m.sig.receiver().is_none() {
+                    error(
+                        ident.span(),
+                        format!(
+                            "associated function `{}` has no `self` receiver, which is not dyn-compatible; {}",
+                            ident, suggestion
+                        ),
+                    );
+                }
+                if let Some(param) = generic {
+                    error(
+                        param.span(),
+                        format!(
+                            "method `{}` has generic parameters, which is not dyn-compatible; {}",
+                            ident, suggestion
+                        ),
+                    );
+                }
+                if let Some(ref async_) = m.sig.asyncness {
+                    error(
+                        async_.span(),
+                        format!(
+                            "method `{}` is `async`, which is not dyn-compatible; {}, or `#[blanket(boxed_futures)]` to the trait",
+                            ident, suggestion
+                        ),
+                    );
+                }
+                if let Some(span) = self_arguments.first() {
+                    error(
+                        *span,
+                        format!(
+                            "method `{}` takes `Self` as an argument, which is not dyn-compatible; {}",
+                            ident, suggestion
+                        ),
+                    );
+                }
+                if let Some(span) = finder.self_types.first() {
+                    error(
+                        *span,
+                        format!(
+                            "method `{}` returns `Self`, which is not dyn-compatible; {}",
+                            ident, suggestion
+                        ),
+                    );
+                }
+                if let Some(span) = finder.impl_traits.first() {
+                    error(
+                        *span,
+                        format!(
+                            "method `{}` uses `impl Trait`, which is not dyn-compatible; {}, or return a boxed trait object",
+                            ident, suggestion
+                        ),
+                    );
+                }
+            }
+            _ => (),
+        }
+    }
+
+    if let Some(mut err) = errors.into_iter().reduce(|mut acc, e| {
+        acc.combine(e);
+        acc
+    }) {
+        let note = format!("`{}` cannot be used as a trait object", trait_ident);
+        err.combine(syn::Error::new(trait_ident.span(), note));
+        return Err(err);
+    }
+
+    // a function taking the trait object only compiles if it is well-formed
+    let lifetime = syn::Lifetime::new("'blanket", trait_ident.span());
+    let dyn_type = trait_object_type(trait_ident, &trait_.generics, &assoc_types, &lifetime)?;
+    let mut generics = trait_.generics.clone();
+    generics.params.insert(0, parse_quote!(#lifetime));
+    generics.params.extend(
+        assoc_types
+            .into_iter()
+            .map(|(_, p)| syn::GenericParam::Type(p)),
+    );
+    let where_clause = &generics.where_clause;
+    Ok(parse_quote!(
+        const _: () = {
+            #[allow(dead_code)]
+            fn assert_dyn_compatible #generics (_: &(#dyn_type)) #where_clause {}
+        };
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use syn::parse_quote;
+
+    use super::DynCompatible;
+
+    #[test]
+    #[rustfmt::skip]
+    fn derive() {
+        let dyn_compatible = DynCompatible { ident: parse_quote!(DynPlugin) };
+        let trait_ = parse_quote!(
+            trait Plugin {
+                type Output;
+                fn name(&self) -> String;
+                fn new() -> Self where Self: Sized;
+                fn process(&mut self, input: &[u8]) -> Self::Output;
+            }
+        );
+        let items = dyn_compatible.derive(&trait_, &Default::default()).unwrap();
+        assert_eq!(items.len(), 3);
+        assert_eq!(
+            items[0],
+            parse_quote!(
+                trait DynPlugin {
+                    type Output;
+                    fn name(&self) -> String;
+                    fn process(&mut self, input: &[u8]) -> Self::Output;
+                }
+            )
+        );
+        assert_eq!(
+            items[1],
+            parse_quote!(
+                #[automatically_derived]
+                impl<P: Plugin> DynPlugin for P {
+                    type Output = <P as Plugin>::Output;
+                    #[inline]
+                    fn name(&self) -> String {
+                        <P as Plugin>::name(self)
+                    }
+                    #[inline]
+                    fn process(&mut self, input: &[u8]) -> Self::Output {
+                        <P as Plugin>::process(self, input)
+                    }
+                }
+            )
+        );
+        assert_eq!(
+            items[2],
+            parse_quote!(
+                #[automatically_derived]
+                impl<'blanket, Output> Plugin for dyn DynPlugin<Output = Output> + 'blanket {
+                    type Output = Output;
+                    #[inline]
+                    fn name(&self) -> String {
+                        <Self as DynPlugin>::name(self)
+                    }
+                    #[inline]
+                    fn process(&mut self, input: &[u8]) -> Self::Output {
+                        <Self as DynPlugin>::process(self, input)
+                    }
+                }
+            )
+        );
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn derive_erased_several_traits() {
+        let dyn_compatible = DynCompatible { ident: parse_quote!(DynPlugin) };
+        let trait_ = parse_quote!(
+            trait Plugin {
+                fn log<D: Debug + Display + ?Sized>(&self, message: &D);
+                fn name(&self) -> impl Display + Send + Sync;
+            }
+        );
+        let items = dyn_compatible.derive(&trait_, &Default::default()).unwrap();
+        assert_eq!(
+            items[0],
+            parse_quote!(
+                trait DynPlugin {
+                    fn name<'life0, 'blanket>(
+                        &'life0 self
+                    ) -> std::boxed::Box<dyn Display + Send + Sync + 'blanket>
+                    where
+                        'life0: 'blanket,
+                        Self: 'blanket;
+                }
+            )
+        );
+
+        let trait_ = parse_quote!(
+            trait Plugin {
+                fn name(&self) -> impl Debug + Display;
+                fn log<D: Debug + Display + ?Sized>(&self, message: &D);
+                fn id(&self) -> u32;
+            }
+        );
+        let items = dyn_compatible.derive(&trait_, &Default::default()).unwrap();
+        assert_eq!(
+            items[0],
+            parse_quote!(
+                trait DynPlugin {
+                    fn id(&self) -> u32;
+                }
+            )
+        );
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn derive_erased() {
+        let dyn_compatible = DynCompatible { ident: parse_quote!(DynPlugin) };
+        let trait_ = parse_quote!(
+            trait Plugin {
+                fn log<D: std::fmt::Display + ?Sized>(&self, message: &D);
+                fn convert<T: From<u8>>(&self) -> T;
+                fn into_name(self) -> String;
+                async fn run(&self);
+                fn iter(&self) -> impl Iterator<Item = u8> + '_;
+            }
+        );
+        let items = dyn_compatible.derive(&trait_, &Default::default()).unwrap();
+        assert_eq!(items.len(), 2);
+        assert_eq!(
+            items[0],
+            parse_quote!(
+                trait DynPlugin {
+                    fn log(&self, message: &dyn std::fmt::Display);
+                    fn into_name(self: std::boxed::Box<Self>) -> String;
+                    fn run<'life0, 'blanket>(
+                        &'life0 self
+                    ) -> std::pin::Pin<std::boxed::Box<dyn std::future::Future<Output = ()> + 'blanket>>
+                    where
+                        'life0: 'blanket,
+                        Self: 'blanket;
+                    fn iter<'life0, 'blanket>(
+                        &'life0 self
+                    ) -> std::boxed::Box<dyn Iterator<Item = u8> + 'blanket>
+                    where
+                        'life0: 'blanket,
+                        Self: 'blanket;
+                }
+            )
+        );
+        assert_eq!(
+            items[1],
+            parse_quote!(
+                #[automatically_derived]
+                impl<P: Plugin> DynPlugin for P {
+                    #[inline]
+                    fn log(&self, message: &dyn std::fmt::Display) {
+                        <P as Plugin>::log(self, message)
+                    }
+                    #[inline]
+                    fn into_name(self: std::boxed::Box<Self>) -> String {
+                        <P as Plugin>::into_name(*self)
+                    }
+                    #[inline]
+                    fn run<'life0, 'blanket>(
+                        &'life0 self
+                    ) -> std::pin::Pin<std::boxed::Box<dyn std::future::Future<Output = ()> + 'blanket>>
+                    where
+                        'life0: 'blanket,
+                        Self: 'blanket
+                    {
+                        std::boxed::Box::pin(<P as Plugin>::run(self))
+                    }
+                    #[inline]
+                    fn iter<'life0, 'blanket>(
+                        &'life0 self
+                    ) -> std::boxed::Box<dyn Iterator<Item = u8> + 'blanket>
+                    where
+                        'life0: 'blanket,
+                        Self: 'blanket
+                    {
+                        std::boxed::Box::new(<P as Plugin>::iter(self))
+                    }
+                }
+            )
+        );
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn assert_dyn_compatible() {
+        let trait_ = parse_quote!(
+            trait Source<T> {
+                type Item;
+                fn new() -> Self
+                where
+                    Self: Sized;
+                fn get(&self, index: T) -> Self::Item;
+            }
+        );
+        assert_eq!(
+            super::assert_dyn_compatible(&trait_).unwrap(),
+            parse_quote!(
+                const _: () = {
+                    #[allow(dead_code)]
+                    fn assert_dyn_compatible<'blanket, T, Item>(
+                        _: &(dyn Source<T, Item = Item> + 'blanket)
+                    ) {}
+                };
+            )
+        );
+    }
+
+    #[test]
+    fn assert_dyn_compatible_violations() {
+        let trait_ = parse_quote!(
+            trait Source: Sized {
+                const LEN: usize;
+                fn new() -> Self;
+                fn get<T>(&self, index: T) -> impl Clone;
+            }
+        );
+        let err = super::assert_dyn_compatible(&trait_).unwrap_err();
+        let messages = err.into_iter().map(|e| e.to_string()).collect::<Vec<_>>();
+        assert_eq!(messages.len(), 7);
+        assert!(messages[2].starts_with("associated function `new` has no `self` receiver"));
+        assert!(messages[3].starts_with("method `new` returns `Self`"));
+        assert!(messages[4].starts_with("method `get` has generic parameters"));
+        assert!(messages[5].starts_with("method `get` uses `impl Trait`"));
+    }
+}
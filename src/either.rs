@@ -0,0 +1,480 @@
+use syn::parse_quote;
+use syn::spanned::Spanned;
+
+use crate::derive::await_expr;
+use crate::derive::Options;
+use crate::fanout::check_self;
+use crate::utils::fresh_ident;
+use crate::utils::fresh_type_ident;
+use crate::utils::generics_declaration_to_generics;
+use crate::utils::is_self_type;
+use crate::utils::signature_to_method_call;
+
+/// Check whether the given type is exactly `Box<Self>`.
+fn is_boxed_self(ty: &syn::Type) -> bool {
+    let segment = match ty {
+        syn::Type::Path(p) if p.qself.is_none() => p.path.segments.last(),
+        _ => None,
+    };
+    match segment {
+        Some(s) if s.ident == "Box" => match s.arguments {
+            syn::PathArguments::AngleBracketed(ref a) if a.args.len() == 1 => {
+                matches!(a.args[0], syn::GenericArgument::Type(ref ty) if is_self_type(ty))
+            }
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// An enum holding one of several implementors of a trait.
+///
+/// Given `FooEither`, generate `enum FooEither<L, R> { Left(L), Right(R) }`
+/// implementing the trait when both `L` and `R` do. Given a list of types,
+/// such as `Codecs(Gzip, Zstd)`, generate `enum Codecs { Gzip(Gzip), Zstd(Zstd) }`
+/// for a closed set of implementors instead.
+pub struct Either {
+    pub ident: syn::Ident,
+    /// The concrete types of the variants, if given.
+    pub types: Option<Vec<syn::Path>>,
+}
+
+impl Either {
+    /// Parse the value of an `either = ...` argument.
+    pub fn from_expr(expr: &syn::Expr) -> syn::Result<Self> {
+        let path_ident = |expr: &syn::Expr| match expr {
+            syn::Expr::Path(ref p) if p.qself.is_none() => p.path.get_ident().cloned(),
+            _ => None,
+        };
+        if let Some(ident) = path_ident(expr) {
+            return Ok(Self { ident, types: None });
+        }
+
+        let call = match expr {
+            syn::Expr::Call(ref call) => call,
+            _ => return Err(syn::Error::new(expr.span(), "expected enum identifier")),
+        };
+        let ident = path_ident(&call.func)
+            .ok_or_else(|| syn::Error::new(call.func.span(), "expected enum identifier"))?;
+        let mut types: Vec<syn::Path> = Vec::new();
+        for arg in call.args.iter() {
+            match arg {
+                syn::Expr::Path(ref p) if p.qself.is_none() => {
+                    let variant = &p.path.segments.last().unwrap().ident;
+                    if types
+                        .iter()
+                        .any(|t| t.segments.last().unwrap().ident == *variant)
+                    {
+                        let msg = format!("duplicate variant `{}` given", variant);
+                        return Err(syn::Error::new(p.span(), msg));
+                    }
+                    types.push(p.path.clone());
+                }
+                _ => return Err(syn::Error::new(arg.span(), "expected type path")),
+            }
+        }
+        if types.is_empty() {
+            let msg = "expected at least one type";
+            return Err(syn::Error::new(call.paren_token.span.join(), msg));
+        }
+        Ok(Self {
+            ident,
+            types: Some(types),
+        })
+    }
+
+    /// Get the variants of the enum, along with the type they hold.
+    fn variants(&self, trait_: &syn::ItemTrait) -> Vec<(syn::Ident, syn::Type)> {
+        match self.types {
+            Some(ref types) => types
+                .iter()
+                .map(|path| {
+                    (
+                        path.segments.last().unwrap().ident.clone(),
+                        parse_quote!(#path),
+                    )
+                })
+                .collect(),
+            None => ["Left", "Right"]
+                .iter()
+                .map(|variant| {
+                    let ty = fresh_type_ident(trait_, &variant[..1]);
+                    (
+                        syn::Ident::new(variant, self.ident.span()),
+                        parse_quote!(#ty),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    /// Generate the implementation of a method matching on the variants.
+    fn derive_method(
+        &self,
+        m: &syn::TraitItemFn,
+        variants: &[(syn::Ident, syn::Type)],
+        options: &Options,
+    ) -> syn::Result<syn::ImplItemFn> {
+        let name = self.ident.to_string();
+        // typed receivers are supported as long as the variant can be
+        // matched from them, a boxed value being unboxed and boxed again
+        let boxed = match m.sig.receiver() {
+            Some(r) if r.colon_token.is_none() => false,
+            Some(r) => match *r.ty {
+                syn::Type::Reference(ref ty) if is_self_type(&ty.elem) => false,
+                ref ty if is_self_type(ty) => false,
+                ref ty if is_boxed_self(ty) => true,
+                _ => {
+                    let msg = format!(
+                        "cannot derive `{}` for a trait declaring methods with arbitrary receiver types",
+                        name
+                    );
+                    return Err(syn::Error::new(r.span(), msg));
+                }
+            },
+            None => {
+                let msg = format!(
+                    "cannot derive `{}` for a trait declaring associated functions",
+                    name
+                );
+                return Err(syn::Error::new(m.sig.ident.span(), msg));
+            }
+        };
+
+        // a method returning `Self` wraps its result in the same variant, but
+        // every variant must return the same type otherwise
+        let returns_self = match m.sig.output {
+            syn::ReturnType::Type(_, ref ty) if is_self_type(ty) => true,
+            syn::ReturnType::Type(_, ref ty) if matches!(**ty, syn::Type::ImplTrait(_)) => {
+                let msg = format!(
+                    "cannot derive `{}` for a method returning `impl Trait`",
+                    name
+                );
+                return Err(syn::Error::new(ty.span(), msg));
+            }
+            _ => false,
+        };
+        check_self(&m.sig, &name, !returns_self)?;
+
+        let ident = &self.ident;
+        let inner = fresh_ident(&m.sig, "inner");
+        let mut call = signature_to_method_call(&m.sig)?;
+        call.receiver = if boxed {
+            Box::new(parse_quote!(::std::boxed::Box::new(#inner)))
+        } else {
+            Box::new(parse_quote!(#inner))
+        };
+        let call = await_expr(call.into(), &m.sig);
+        let scrutinee: syn::Expr = if boxed {
+            parse_quote!(*self)
+        } else {
+            parse_quote!(self)
+        };
+        let arms = variants.iter().map(|(variant, _)| -> syn::Arm {
+            if returns_self {
+                parse_quote!(#ident::#variant(#inner) => #ident::#variant(#call),)
+            } else {
+                parse_quote!(#ident::#variant(#inner) => #call,)
+            }
+        });
+
+        let signature = &m.sig;
+        let mut attrs = options.forwarded_attrs(&m.attrs);
+        attrs.extend(options.inline.attribute());
+        Ok(parse_quote!(
+            #(#attrs)*
+            #signature {
+                match #scrutinee {
+                    #(#arms)*
+                }
+            }
+        ))
+    }
+
+    /// Generate the enum and its implementation of the trait.
+    pub fn derive(
+        &self,
+        trait_: &syn::ItemTrait,
+        options: &Options,
+    ) -> syn::Result<Vec<syn::Item>> {
+        let trait_ident = &trait_.ident;
+        let ident = &self.ident;
+        let vis = &trait_.vis;
+        let variants = self.variants(trait_);
+        let (first, first_ty) = &variants[0];
+
+        let mut trait_generic_names = trait_.generics.clone();
+        trait_generic_names.params = generics_declaration_to_generics(&trait_.generics.params)?;
+        let trait_args = trait_generic_names.params.iter().collect::<Vec<_>>();
+
+        // the associated types are taken from the first variant, and must
+        // be the same for all other variants
+        let mut methods: Vec<syn::ImplItemFn> = Vec::new();
+        let mut assoc_types: Vec<syn::ImplItemType> = Vec::new();
+        let mut bindings: Vec<syn::GenericArgument> = Vec::new();
+        let mut errors: Option<syn::Error> = None;
+        for item in trait_.items.iter() {
+            let result = match item {
+                syn::TraitItem::Fn(m) => self
+                    .derive_method(m, &variants, options)
+                    .map(|method| methods.push(method)),
+                syn::TraitItem::Type(t) if !t.generics.params.is_empty() => {
+                    let msg = format!(
+                        "cannot derive `{}` for a trait declaring generic associated types",
+                        ident
+                    );
+                    Err(syn::Error::new(t.span(), msg))
+                }
+                syn::TraitItem::Type(t) => {
+                    let t_ident = &t.ident;
+                    let ty: syn::Type =
+                        parse_quote!(<#first_ty as #trait_ident #trait_generic_names>::#t_ident);
+                    assoc_types.push(parse_quote!(type #t_ident = #ty;));
+                    bindings.push(parse_quote!(#t_ident = #ty));
+                    Ok(())
+                }
+                _ => Ok(()),
+            };
+            if let Err(e) = result {
+                match errors {
+                    Some(ref mut err) => err.combine(e),
+                    None => errors = Some(e),
+                }
+            }
+        }
+        if let Some(err) = errors {
+            return Err(err);
+        }
+
+        // every variant must implement the trait
+        let mut where_clause = trait_.generics.where_clause.clone();
+        let predicates = &mut where_clause
+            .get_or_insert_with(|| parse_quote!(where))
+            .predicates;
+        for (variant, ty) in variants.iter() {
+            if variant == first || bindings.is_empty() {
+                predicates.push(parse_quote!(#ty: #trait_ident #trait_generic_names));
+            } else {
+                predicates.push(parse_quote!(#ty: #trait_ident<#(#trait_args,)* #(#bindings),*>));
+            }
+        }
+        predicates.extend(options.predicates.iter().cloned());
+        predicates.push_punct(Default::default());
+
+        let mut impl_generics = trait_.generics.clone();
+        let enum_generics = match self.types {
+            Some(_) => None,
+            None => {
+                let params = variants.iter().map(|(_, ty)| ty).collect::<Vec<_>>();
+                for ty in params.iter() {
+                    impl_generics.params.push(parse_quote!(#ty));
+                }
+                Some(quote::quote!(<#(#params),*>))
+            }
+        };
+
+        let doc = match self.types {
+            Some(_) => format!(" One of the implementors of [`{}`].", trait_ident),
+            None => format!(" Either of two implementors of [`{}`].", trait_ident),
+        };
+        let variants_decl = variants
+            .iter()
+            .map(|(variant, ty)| quote::quote!(#variant(#ty)));
+        let cfg = options.cfg.as_ref().map(|cfg| quote::quote!(#[cfg(#cfg)]));
+        let mut items: Vec<syn::Item> = vec![
+            parse_quote!(
+                #cfg
+                #[doc = #doc]
+                #vis enum #ident #enum_generics {
+                    #(#variants_decl,)*
+                }
+            ),
+            parse_quote!(
+                #cfg
+                #[automatically_derived]
+                impl #impl_generics #trait_ident #trait_generic_names for #ident #enum_generics #where_clause {
+                    #(#assoc_types)*
+                    #(#methods)*
+                }
+            ),
+        ];
+
+        // the variants of a closed set can be built from the types they hold
+        if self.types.is_some() {
+            for (variant, ty) in variants.iter() {
+                items.push(parse_quote!(
+                    #cfg
+                    #[automatically_derived]
+                    impl ::core::convert::From<#ty> for #ident {
+                        #[inline]
+                        fn from(value: #ty) -> Self {
+                            #ident::#variant(value)
+                        }
+                    }
+                ));
+            }
+        }
+
+        Ok(items)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use syn::parse_quote;
+
+    use super::Either;
+
+    #[test]
+    fn derive() {
+        let trait_ = parse_quote!(
+            pub trait Storage<K> {
+                type Value;
+                fn get(&self, key: &K) -> Option<Self::Value>;
+                fn clear(&mut self);
+            }
+        );
+        let either = Either::from_expr(&parse_quote!(StorageEither)).unwrap();
+        let items = either.derive(&trait_, &Default::default()).unwrap();
+        assert_eq!(
+            items[0],
+            parse_quote!(
+                #[doc = " Either of two implementors of [`Storage`]."]
+                pub enum StorageEither<L, R> {
+                    Left(L),
+                    Right(R),
+                }
+            )
+        );
+        assert_eq!(
+            items[1],
+            parse_quote!(
+                #[automatically_derived]
+                impl<K, L, R> Storage<K> for StorageEither<L, R>
+                where
+                    L: Storage<K>,
+                    R: Storage<K, Value = <L as Storage<K>>::Value>,
+                {
+                    type Value = <L as Storage<K>>::Value;
+                    #[inline]
+                    fn get(&self, key: &K) -> Option<Self::Value> {
+                        match self {
+                            StorageEither::Left(inner) => inner.get(key),
+                            StorageEither::Right(inner) => inner.get(key),
+                        }
+                    }
+                    #[inline]
+                    fn clear(&mut self) {
+                        match self {
+                            StorageEither::Left(inner) => inner.clear(),
+                            StorageEither::Right(inner) => inner.clear(),
+                        }
+                    }
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn derive_types() {
+        let trait_ = parse_quote!(
+            trait Codec {
+                async fn with_level(self, level: u8) -> Self;
+            }
+        );
+        let either = Either::from_expr(&parse_quote!(Codecs(Gzip, codecs::Zstd))).unwrap();
+        let items = either.derive(&trait_, &Default::default()).unwrap();
+        assert_eq!(
+            items[0],
+            parse_quote!(
+                #[doc = " One of the implementors of [`Codec`]."]
+                enum Codecs {
+                    Gzip(Gzip),
+                    Zstd(codecs::Zstd),
+                }
+            )
+        );
+        assert_eq!(
+            items[1],
+            parse_quote!(
+                #[automatically_derived]
+                impl Codec for Codecs
+                where
+                    Gzip: Codec,
+                    codecs::Zstd: Codec,
+                {
+                    #[inline]
+                    async fn with_level(self, level: u8) -> Self {
+                        match self {
+                            Codecs::Gzip(inner) => Codecs::Gzip(inner.with_level(level).await),
+                            Codecs::Zstd(inner) => Codecs::Zstd(inner.with_level(level).await),
+                        }
+                    }
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn derive_receivers() {
+        let trait_ = parse_quote!(
+            trait Codec {
+                fn level(self: &Self) -> u8;
+                fn into_level(self: Box<Self>) -> u8;
+            }
+        );
+        let either = Either::from_expr(&parse_quote!(Codecs(Gzip))).unwrap();
+        let items = either.derive(&trait_, &Default::default()).unwrap();
+        assert_eq!(items.len(), 3);
+        assert_eq!(
+            items[1],
+            parse_quote!(
+                #[automatically_derived]
+                impl Codec for Codecs
+                where
+                    Gzip: Codec,
+                {
+                    #[inline]
+                    fn level(self: &Self) -> u8 {
+                        match self {
+                            Codecs::Gzip(inner) => inner.level(),
+                        }
+                    }
+                    #[inline]
+                    fn into_level(self: Box<Self>) -> u8 {
+                        match *self {
+                            Codecs::Gzip(inner) => ::std::boxed::Box::new(inner).into_level(),
+                        }
+                    }
+                }
+            )
+        );
+        assert_eq!(
+            items[2],
+            parse_quote!(
+                #[automatically_derived]
+                impl ::core::convert::From<Gzip> for Codecs {
+                    #[inline]
+                    fn from(value: Gzip) -> Self {
+                        Codecs::Gzip(value)
+                    }
+                }
+            )
+        );
+
+        let trait_ = parse_quote!(
+            trait Codec {
+                fn level(self: Rc<Self>) -> u8;
+            }
+        );
+        assert!(either.derive(&trait_, &Default::default()).is_err());
+    }
+
+    #[test]
+    fn from_expr() {
+        assert!(Either::from_expr(&parse_quote!(Codecs())).is_err());
+        assert!(Either::from_expr(&parse_quote!(Codecs(Gzip, gzip::Gzip))).is_err());
+        assert!(Either::from_expr(&parse_quote!(codecs::Codecs)).is_err());
+    }
+}
@@ -0,0 +1,327 @@
+use std::collections::HashSet;
+
+use proc_macro2::{Group, Spacing, TokenStream, TokenTree};
+use quote::ToTokens;
+use syn::parse_quote;
+use syn::visit_mut::VisitMut;
+
+use crate::derive::Options;
+use crate::utils::signature_to_function_call;
+use crate::utils::SelfReplacer;
+
+/// Collect all identifiers in a token stream, including inside groups.
+fn collect_idents(tokens: TokenStream, idents: &mut HashSet<String>) {
+    for tree in tokens {
+        match tree {
+            TokenTree::Ident(ident) => {
+                idents.insert(ident.to_string());
+            }
+            TokenTree::Group(group) => collect_idents(group.stream(), idents),
+            _ => (),
+        }
+    }
+}
+
+/// Rewrite a method body so that it can be used in a function of a child module.
+///
+/// The `self` value is replaced with `receiver`, the `Self` type with
+/// `generic_type`, and relative paths starting with `self::` or `super::`
+/// get an additional `super::` to remain relative to the trait module. The
+/// body is rewritten as tokens, so that macro invocations are also updated.
+fn rewrite_body(
+    tokens: TokenStream,
+    receiver: &syn::Ident,
+    generic_type: &syn::Ident,
+) -> TokenStream {
+    let trees = tokens.into_iter().collect::<Vec<_>>();
+    let mut out = Vec::with_capacity(trees.len());
+    for (i, tree) in trees.iter().enumerate() {
+        match tree {
+            TokenTree::Ident(ident) => {
+                // check whether the identifier starts a path, and is not
+                // already in the middle of one
+                let is_path_start = matches!(
+                    trees.get(i + 1),
+                    Some(TokenTree::Punct(p)) if p.as_char() == ':' && p.spacing() == Spacing::Joint
+                ) && !matches!(
+                    i.checked_sub(1).and_then(|j| trees.get(j)),
+                    Some(TokenTree::Punct(p)) if p.as_char() == ':'
+                );
+                if ident == "self" && is_path_start {
+                    out.push(TokenTree::Ident(syn::Ident::new("super", ident.span())));
+                } else if ident == "super" && is_path_start {
+                    out.extend(quote::quote_spanned!(ident.span()=> super::super));
+                } else if ident == "self" {
+                    out.push(TokenTree::Ident(syn::Ident::new(
+                        &receiver.to_string(),
+                        ident.span(),
+                    )));
+                } else if ident == "Self" {
+                    out.push(TokenTree::Ident(syn::Ident::new(
+                        &generic_type.to_string(),
+                        ident.span(),
+                    )));
+                } else {
+                    out.push(tree.clone());
+                }
+            }
+            TokenTree::Group(group) => {
+                let stream = rewrite_body(group.stream(), receiver, generic_type);
+                let mut new = Group::new(group.delimiter(), stream);
+                new.set_span(group.span());
+                out.push(TokenTree::Group(new));
+            }
+            _ => out.push(tree.clone()),
+        }
+    }
+    out.into_iter().collect()
+}
+
+/// Move the provided bodies of the methods of `trait_` to functions of a new `module`.
+///
+/// Each method with a body gets a function with the same name in `module`,
+/// taking the receiver as its first argument, and the method body is
+/// replaced with a call to that function, so that implementors overriding
+/// the method can still call the default implementation. The functions
+/// take the receiver as the generic type given in `options`, if any.
+pub fn extract_defaults(
+    mut trait_: syn::ItemTrait,
+    module: &syn::Ident,
+    options: &Options,
+) -> syn::Result<(syn::ItemTrait, syn::ItemMod)> {
+    let generic_type = options.generic_type(&trait_);
+    let trait_ident = &trait_.ident;
+    let (_, trait_generics, _) = trait_.generics.split_for_impl();
+
+    let mut functions: Vec<syn::ItemFn> = Vec::new();
+    for item in trait_.items.iter() {
+        let m = match item {
+            syn::TraitItem::Fn(m) => m,
+            _ => continue,
+        };
+        let block = match m.default {
+            Some(ref block) => block,
+            None => continue,
+        };
+
+        // name the receiver after the generic type, without shadowing any
+        // other identifier used by the method
+        let mut idents = HashSet::new();
+        collect_idents(m.to_token_stream(), &mut idents);
+        let mut receiver = generic_type.to_string().to_lowercase();
+        while idents.contains(&receiver) {
+            receiver.push('_');
+        }
+        let receiver = syn::Ident::new(&receiver, m.sig.ident.span());
+
+        // build the function signature from the method signature
+        let mut sig = m.sig.clone();
+        if let Some(syn::FnArg::Receiver(r)) = sig.inputs.first() {
+            let mutability = r.mutability.filter(|_| r.reference.is_none());
+            let ty = &r.ty;
+            let arg: syn::FnArg = parse_quote!(#mutability #receiver: #ty);
+            sig.inputs[0] = arg;
+        }
+        let sized = m
+            .sig
+            .receiver()
+            .is_some_and(|r| r.reference.is_none() && r.colon_token.is_none());
+        let bound: syn::TypeParam = if sized {
+            parse_quote!(#generic_type: #trait_ident #trait_generics)
+        } else {
+            parse_quote!(#generic_type: #trait_ident #trait_generics + ?Sized)
+        };
+        let mut generics = trait_.generics.clone();
+        generics.params.push(syn::GenericParam::Type(bound));
+        generics.params.extend(sig.generics.params.iter().cloned());
+        if let Some(ref where_clause) = sig.generics.where_clause {
+            let predicates = where_clause.predicates.iter().cloned();
+            generics.make_where_clause().predicates.extend(predicates);
+        }
+        sig.generics = generics;
+        SelfReplacer {
+            generic_type: &generic_type,
+        }
+        .visit_signature_mut(&mut sig);
+
+        // rewrite the body to use the receiver argument
+        let body = rewrite_body(block.to_token_stream(), &receiver, &generic_type);
+        let block: syn::Block = syn::parse2(body)?;
+        let attrs = m
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("doc") || attr.path().is_ident("cfg"))
+            .collect::<Vec<_>>();
+        functions.push(parse_quote!(
+            #(#attrs)*
+            #[allow(unused_variables)]
+            pub #sig #block
+        ));
+    }
+
+    // replace the provided bodies with calls to the extracted functions
+    let original = trait_.clone();
+    for item in trait_.items.iter_mut() {
+        if let syn::TraitItem::Fn(ref mut m) = item {
+            if m.default.is_none() {
+                continue;
+            }
+            let mut call = signature_to_function_call(&m.sig)?;
+            let ident = &m.sig.ident;
+            call.func = if m.sig.receiver().is_some() {
+                Box::new(parse_quote!(#module::#ident))
+            } else {
+                // without a receiver, the generic type cannot be inferred
+                // and must be given explicitly, after the trait parameters
+                let mut args: Vec<syn::GenericArgument> = Vec::new();
+                for param in original.generics.params.iter() {
+                    match param {
+                        syn::GenericParam::Type(t) => {
+                            let ident = &t.ident;
+                            args.push(parse_quote!(#ident));
+                        }
+                        syn::GenericParam::Const(c) => {
+                            let ident = &c.ident;
+                            args.push(parse_quote!(#ident));
+                        }
+                        syn::GenericParam::Lifetime(_) => (),
+                    }
+                }
+                args.push(parse_quote!(Self));
+                for param in m.sig.generics.params.iter() {
+                    if !matches!(param, syn::GenericParam::Lifetime(_)) {
+                        args.push(parse_quote!(_));
+                    }
+                }
+                Box::new(parse_quote!(#module::#ident::<#(#args),*>))
+            };
+            let expr: syn::Expr = match m.sig.asyncness {
+                Some(_) => parse_quote!(#call.await),
+                None => call.into(),
+            };
+            m.default = Some(parse_quote!({ #expr }));
+        }
+    }
+
+    let vis = &trait_.vis;
+    let doc = format!(
+        " Default implementations of the [`{}`] methods.",
+        trait_.ident
+    );
+    let module: syn::ItemMod = parse_quote!(
+        #[doc = #doc]
+        #vis mod #module {
+            #[allow(unused_imports)]
+            use super::*;
+
+            #(#functions)*
+        }
+    );
+    Ok((trait_, module))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use syn::parse_quote;
+
+    #[test]
+    fn extract_defaults() {
+        let trait_ = parse_quote!(
+            pub trait Visitor {
+                /// Visit a string.
+                fn visit_str(&self, s: &str) {
+                    for c in s.chars() {
+                        self.visit_char(c);
+                    }
+                }
+                fn visit_char(&self, c: char);
+                fn visit_all(&mut self, v: Vec<Self>) -> Self {
+                    println!("{}", self::NAME);
+                    Self::new(v)
+                }
+                fn name<T: Into<String>>() -> String {
+                    String::new()
+                }
+            }
+        );
+        let (trait_, module) =
+            super::extract_defaults(trait_, &parse_quote!(visit), &Default::default()).unwrap();
+        assert_eq!(
+            trait_,
+            parse_quote!(
+                pub trait Visitor {
+                    /// Visit a string.
+                    fn visit_str(&self, s: &str) {
+                        visit::visit_str(self, s)
+                    }
+                    fn visit_char(&self, c: char);
+                    fn visit_all(&mut self, v: Vec<Self>) -> Self {
+                        visit::visit_all(self, v)
+                    }
+                    fn name<T: Into<String>>() -> String {
+                        visit::name::<Self, _>()
+                    }
+                }
+            )
+        );
+        assert_eq!(
+            module,
+            parse_quote!(
+                #[doc = " Default implementations of the [`Visitor`] methods."]
+                pub mod visit {
+                    #[allow(unused_imports)]
+                    use super::*;
+
+                    /// Visit a string.
+                    #[allow(unused_variables)]
+                    pub fn visit_str<V: Visitor + ?Sized>(v: &V, s: &str) {
+                        for c in s.chars() {
+                            v.visit_char(c);
+                        }
+                    }
+                    #[allow(unused_variables)]
+                    pub fn visit_all<V: Visitor + ?Sized>(v_: &mut V, v: Vec<V>) -> V {
+                        println!("{}", super::NAME);
+                        V::new(v)
+                    }
+                    #[allow(unused_variables)]
+                    pub fn name<V: Visitor + ?Sized, T: Into<String>>() -> String {
+                        String::new()
+                    }
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn extract_defaults_generic() {
+        let trait_ = parse_quote!(
+            pub trait Visitor {
+                fn visit_str(&self, s: &str) {
+                    self.visit_chars(s.chars())
+                }
+            }
+        );
+        let options = crate::derive::Options {
+            generic_type: Some(parse_quote!(W)),
+            ..Default::default()
+        };
+        let (_, module) = super::extract_defaults(trait_, &parse_quote!(visit), &options).unwrap();
+        assert_eq!(
+            module,
+            parse_quote!(
+                #[doc = " Default implementations of the [`Visitor`] methods."]
+                pub mod visit {
+                    #[allow(unused_imports)]
+                    use super::*;
+
+                    #[allow(unused_variables)]
+                    pub fn visit_str<W: Visitor + ?Sized>(w: &W, s: &str) {
+                        w.visit_chars(s.chars())
+                    }
+                }
+            )
+        );
+    }
+}
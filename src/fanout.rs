@@ -0,0 +1,323 @@
+use syn::parse_quote;
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::visit::Visit;
+
+use crate::derive::await_expr;
+use crate::derive::Options;
+use crate::derive::WrapperType;
+use crate::utils::fresh_ident;
+use crate::utils::signature_to_method_call;
+
+/// A strategy combining the results of a method called on several values.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Combine {
+    /// Return whether all the calls returned `true`.
+    All,
+    /// Return whether any of the calls returned `true`.
+    Any,
+    /// Add all the results to the default value of the return type.
+    Default,
+    /// Return the first `Err`, without calling the remaining values.
+    Try,
+}
+
+impl Combine {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "all" => Some(Combine::All),
+            "any" => Some(Combine::Any),
+            "default" => Some(Combine::Default),
+            "try" => Some(Combine::Try),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Combine::All => "all",
+            Combine::Any => "any",
+            Combine::Default => "default",
+            Combine::Try => "try",
+        }
+    }
+}
+
+/// Find the uses of the `Self` type, other than in associated type paths.
+#[derive(Default)]
+struct SelfFinder {
+    found: Option<proc_macro2::Span>,
+}
+
+impl<'ast> Visit<'ast> for SelfFinder {
+    fn visit_type_path(&mut self, ty: &'ast syn::TypePath) {
+        if ty.qself.is_none() && ty.path.is_ident("Self") {
+            self.found.get_or_insert(ty.span());
+        }
+        syn::visit::visit_type_path(self, ty);
+    }
+}
+
+/// Check that a method can be called on several values.
+///
+/// Every value is given the same arguments, which cannot refer to the
+/// wrapper type, and neither can the return type if `output` is set.
+pub fn check_self(sig: &syn::Signature, name: &str, output: bool) -> syn::Result<()> {
+    let mut finder = SelfFinder::default();
+    for input in sig.inputs.iter() {
+        if let syn::FnArg::Typed(t) = input {
+            finder.visit_type(&t.ty);
+        }
+    }
+    if let (true, syn::ReturnType::Type(_, ref ty)) = (output, &sig.output) {
+        finder.visit_type(ty);
+    }
+    match finder.found {
+        Some(span) => {
+            let msg = format!(
+                "cannot derive `{}` for a method using the `Self` type",
+                name
+            );
+            Err(syn::Error::new(span, msg))
+        }
+        None => Ok(()),
+    }
+}
+
+/// Check whether a type is known to implement `Copy`, or to be reborrowed.
+///
+/// Only primitive types, references, pointers, and generic parameters of
+/// the method or `impl Trait` types bounded by `Copy` are known, since the
+/// other types may not implement `Copy` for every implementor of the trait.
+fn is_copy(ty: &syn::Type, generics: &syn::Generics) -> bool {
+    const PRIMITIVES: &[&str] = &[
+        "bool", "char", "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64",
+        "i128", "isize", "f32", "f64",
+    ];
+    let is_copy_bound = |bound: &syn::TypeParamBound| matches!(bound, syn::TypeParamBound::Trait(t) if t.path.segments.last().is_some_and(|s| s.ident == "Copy"));
+    match ty {
+        syn::Type::Reference(_)
+        | syn::Type::Ptr(_)
+        | syn::Type::BareFn(_)
+        | syn::Type::Never(_) => true,
+        syn::Type::Paren(p) => is_copy(&p.elem, generics),
+        syn::Type::Group(g) => is_copy(&g.elem, generics),
+        syn::Type::Array(a) => is_copy(&a.elem, generics),
+        syn::Type::Tuple(t) => t.elems.iter().all(|ty| is_copy(ty, generics)),
+        syn::Type::ImplTrait(i) => i.bounds.iter().any(is_copy_bound),
+        syn::Type::Path(p) if p.qself.is_none() => {
+            let ident = match p.path.get_ident() {
+                Some(ident) => ident,
+                None => return false,
+            };
+            if PRIMITIVES.iter().any(|primitive| ident == primitive) {
+                return true;
+            }
+            let declared = generics
+                .type_params()
+                .any(|param| param.ident == *ident && param.bounds.iter().any(is_copy_bound));
+            let predicated =
+                generics.where_clause.iter().any(|where_clause| {
+                    where_clause.predicates.iter().any(|predicate| match predicate {
+                    syn::WherePredicate::Type(t) => {
+                        matches!(t.bounded_ty, syn::Type::Path(ref p) if p.path.is_ident(ident))
+                            && t.bounds.iter().any(is_copy_bound)
+                    }
+                    _ => false,
+                })
+                });
+            declared || predicated
+        }
+        _ => false,
+    }
+}
+
+/// Check that the arguments of a method can be given to several values.
+///
+/// Every value is given the same arguments, so that the arguments taken by
+/// value must implement `Copy`.
+pub fn check_args(sig: &syn::Signature, name: &str) -> syn::Result<()> {
+    for input in sig.inputs.iter() {
+        if let syn::FnArg::Typed(t) = input {
+            if !is_copy(&t.ty, &sig.generics) {
+                let msg = format!(
+                    "cannot derive `{}` for a method taking an argument by value, since it is given to every value: take it by reference, or use a `Copy` type",
+                    name
+                );
+                return Err(syn::Error::new(t.ty.span(), msg));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Check that the return type of a method can be combined with the given strategy.
+pub fn check_output(sig: &syn::Signature, name: &str, combine: Option<Combine>) -> syn::Result<()> {
+    let ty = match sig.output {
+        syn::ReturnType::Type(_, ref ty) => Some(&**ty),
+        syn::ReturnType::Default => None,
+    };
+    let is_unit = ty.is_none_or(|ty| matches!(ty, syn::Type::Tuple(t) if t.elems.is_empty()));
+    let valid = match combine {
+        None => is_unit,
+        Some(Combine::All) | Some(Combine::Any) => {
+            matches!(ty, Some(syn::Type::Path(p)) if p.qself.is_none() && p.path.is_ident("bool"))
+        }
+        Some(Combine::Default) => !is_unit && !matches!(ty, Some(syn::Type::ImplTrait(_))),
+        Some(Combine::Try) => match ty {
+            Some(syn::Type::Path(p)) if p.qself.is_none() => {
+                let segment = p.path.segments.last().unwrap();
+                segment.ident == "Result"
+                    && match segment.arguments {
+                        syn::PathArguments::AngleBracketed(ref args) => {
+                            matches!(args.args.first(), Some(syn::GenericArgument::Type(syn::Type::Tuple(t))) if t.elems.is_empty())
+                        }
+                        _ => true,
+                    }
+            }
+            _ => false,
+        },
+    };
+    if valid {
+        return Ok(());
+    }
+    let msg = match combine {
+        None => format!(
+            "cannot derive `{}` for a method returning a value, use `#[blanket(combine = ...)]` to combine the results",
+            name
+        ),
+        Some(Combine::All) | Some(Combine::Any) => format!(
+            "`combine = {}` requires a `bool` return type",
+            combine.unwrap().name()
+        ),
+        Some(Combine::Default) => {
+            String::from("`combine = default` requires a return type implementing `Default` and `AddAssign`")
+        }
+        Some(Combine::Try) => String::from("`combine = try` requires a `Result<(), E>` return type"),
+    };
+    let span = match sig.output {
+        syn::ReturnType::Type(_, ref ty) => ty.span(),
+        syn::ReturnType::Default => sig.ident.span(),
+    };
+    Err(syn::Error::new(span, msg))
+}
+
+/// Generate the body of a method calling several values of a wrapper type.
+///
+/// `calls` builds the statements calling every value, given a function which
+/// turns a single call into a statement combining its result with the
+/// results of the previous calls.
+pub fn fan_out(
+    m: &syn::TraitItemFn,
+    name: &str,
+    options: &Options,
+    calls: impl FnOnce(&dyn Fn(syn::Expr) -> syn::Stmt) -> Vec<syn::Stmt>,
+) -> syn::Result<syn::Block> {
+    check_self(&m.sig, name, true)?;
+    check_args(&m.sig, name)?;
+
+    let combine = options.combine(&m.sig.ident);
+    check_output(&m.sig, name, combine)?;
+
+    let result = fresh_ident(&m.sig, "result");
+    let init: Option<syn::Stmt> = match combine {
+        Some(Combine::All) => Some(parse_quote!(let mut #result = true;)),
+        Some(Combine::Any) => Some(parse_quote!(let mut #result = false;)),
+        Some(Combine::Default) => {
+            let ty = match m.sig.output {
+                syn::ReturnType::Type(_, ref ty) => ty,
+                syn::ReturnType::Default => unreachable!(),
+            };
+            Some(parse_quote!(let mut #result: #ty = ::core::default::Default::default();))
+        }
+        Some(Combine::Try) | None => None,
+    };
+    let accumulate = |call: syn::Expr| -> syn::Stmt {
+        match combine {
+            Some(Combine::All) => parse_quote!(#result &= #call;),
+            Some(Combine::Any) => parse_quote!(#result |= #call;),
+            Some(Combine::Default) => parse_quote!(#result += #call;),
+            Some(Combine::Try) => parse_quote!(#call?;),
+            None => parse_quote!(#call;),
+        }
+    };
+    let finish: Option<syn::Expr> = match combine {
+        Some(Combine::Try) => Some(parse_quote!(::core::result::Result::Ok(()))),
+        Some(_) => Some(parse_quote!(#result)),
+        None => None,
+    };
+
+    let stmts = calls(&accumulate);
+    Ok(parse_quote!({
+        #init
+        #(#stmts)*
+        #finish
+    }))
+}
+
+/// Generate the body of a method returning `Self` for a tuple wrapper.
+///
+/// The results of the `calls` to every element are collected into a new
+/// tuple, such as `(self.0.with_level(level), self.1.with_level(level))`.
+pub fn collect(m: &syn::TraitItemFn, name: &str, calls: Vec<syn::Expr>) -> syn::Result<syn::Block> {
+    check_self(&m.sig, name, false)?;
+    check_args(&m.sig, name)?;
+    let mut tuple = calls.into_iter().collect::<Punctuated<_, syn::Token![,]>>();
+    if tuple.len() == 1 {
+        tuple.push_punct(Default::default());
+    }
+    Ok(parse_quote!({ (#tuple) }))
+}
+
+/// Generate the body of a method calling every element of a collection in order.
+pub fn forward_to_elements<W: WrapperType>(
+    m: &syn::TraitItemFn,
+    options: &Options,
+) -> syn::Result<syn::Block> {
+    let r = m.sig.receiver().ok_or_else(|| {
+        let msg = format!(
+            "cannot derive `{}` for a trait declaring associated functions",
+            W::NAME
+        );
+        syn::Error::new(m.sig.span(), msg)
+    })?;
+    W::check_receiver(r)?;
+
+    let iter: syn::Expr = if r.reference.is_none() {
+        parse_quote!(self)
+    } else if r.mutability.is_some() {
+        parse_quote!(self.iter_mut())
+    } else {
+        parse_quote!(self.iter())
+    };
+    let item = fresh_ident(&m.sig, "item");
+    let mut call = signature_to_method_call(&m.sig)?;
+    call.receiver = Box::new(parse_quote!(#item));
+    let call = await_expr(call.into(), &m.sig);
+
+    fan_out(m, W::NAME, options, |accumulate| {
+        let stmt = accumulate(call);
+        vec![parse_quote!(for #item in #iter { #stmt })]
+    })
+}
+
+/// Check that all the methods of the given trait can be fanned out.
+pub fn supports<W: WrapperType>(trait_: &syn::ItemTrait, options: &Options) -> bool {
+    trait_.items.iter().all(|item| match item {
+        syn::TraitItem::Fn(m) => {
+            let overridden = options
+                .overrides
+                .iter()
+                .any(|(ident, _)| *ident == m.sig.ident);
+            let forwarded = m
+                .sig
+                .receiver()
+                .is_some_and(|r| W::check_receiver(r).is_ok())
+                && check_self(&m.sig, W::NAME, true).is_ok()
+                && check_args(&m.sig, W::NAME).is_ok()
+                && check_output(&m.sig, W::NAME, options.combine(&m.sig.ident)).is_ok();
+            overridden || forwarded
+        }
+        _ => true,
+    })
+}
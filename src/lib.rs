@@ -4,91 +4,469 @@ extern crate proc_macro;
 extern crate proc_macro2;
 extern crate quote;
 
-use std::collections::HashSet;
-
 use quote::{quote, ToTokens};
-use syn::{parse_macro_input, punctuated::Punctuated, spanned::Spanned};
+use syn::{parse_macro_input, punctuated::Punctuated, spanned::Spanned, Token};
 
 // ---------------------------------------------------------------------------
 
+mod boxed;
 mod default;
 mod derive;
+mod dyn_compatible;
+mod either;
+mod extract;
+mod fanout;
+mod method;
+mod mut_variant;
 mod types;
 mod utils;
+mod variant;
+mod visitor;
 
 // ---------------------------------------------------------------------------
 
+/// Parse a single derive with its options, such as `Box(inline = never)`.
+///
+/// Derives are parsed as raw identifiers, so that keywords such as `ref` can
+/// get a suggestion.
+fn parse_derive(input: syn::parse::ParseStream) -> syn::Result<(syn::Ident, derive::Options)> {
+    let ident = input.call(syn::ext::IdentExt::parse_any)?;
+    let options = if input.peek(syn::token::Paren) {
+        let content;
+        syn::parenthesized!(content in input);
+        content.parse()?
+    } else {
+        derive::Options::default()
+    };
+    Ok((ident, options))
+}
+
 struct Args {
-    default: Option<syn::Path>,
-    derives: HashSet<types::Type>,
+    assert_dyn_compatible: bool,
+    boxed_futures: Option<boxed::BoxedFutures>,
+    default: Option<default::DefaultModule>,
+    derives: Vec<(types::Type, derive::Options)>,
+    dyn_compatible: Option<dyn_compatible::DynCompatible>,
+    either: Option<either::Either>,
+    extract_defaults: Option<syn::Ident>,
+    mut_variant: Option<mut_variant::MutVariant>,
+    options: derive::Options,
+    variant: Option<variant::Variant>,
 }
 
 impl Args {
-    fn from_meta(arg: &syn::Meta) -> syn::Result<Self> {
+    fn from_metas(args: &Punctuated<syn::Meta, Token![,]>) -> syn::Result<Self> {
+        let mut assert_dyn_compatible = false;
+        let mut boxed_futures = None;
         let mut default = None;
-        let mut derives = HashSet::new();
-
-        match arg {
-            syn::Meta::List(ref l) if l.path.to_token_stream().to_string() == "derive" => {
-                let types = l.parse_args_with(
-                    Punctuated::<syn::Path, syn::Token![,]>::parse_separated_nonempty,
-                )?;
-                for pair in types.into_pairs() {
-                    if let Some(d) = types::Type::from_path(pair.value()) {
-                        derives.insert(d);
-                    } else {
-                        return Err(syn::Error::new(
-                            pair.span(),
-                            "unknown blanket derive option",
-                        ));
+        let mut derives = Vec::new();
+        let mut dyn_compatible = None;
+        let mut either = None;
+        let mut extract_defaults = None;
+        let mut mut_variant = None;
+        let mut options = derive::Options::default();
+        let mut variant = None;
+
+        for arg in args.iter() {
+            match arg {
+                syn::Meta::List(ref l) if l.path.to_token_stream().to_string() == "derive" => {
+                    let types = l.parse_args_with(|input: syn::parse::ParseStream| {
+                        Punctuated::<_, syn::Token![,]>::parse_separated_nonempty_with(
+                            input,
+                            parse_derive,
+                        )
+                    })?;
+                    for (ident, options) in types.into_iter() {
+                        match types::Type::from_str(&ident.to_string()) {
+                            Some(d) if derives.iter().any(|(t, _)| *t == d) => {
+                                let msg = format!("duplicate derive `{}` given", d.name());
+                                return Err(syn::Error::new(ident.span(), msg));
+                            }
+                            Some(d) if options.max.is_some() && d != types::Type::Tuple => {
+                                let msg =
+                                    "the `max` option is only supported by the `Tuple` derive";
+                                return Err(syn::Error::new(ident.span(), msg));
+                            }
+                            Some(types::Type::Fallback)
+                                if derives.iter().any(|(t, _)| *t == types::Type::Tuple) =>
+                            {
+                                let msg = "cannot derive both `Tuple` and `Fallback`, which are both implemented for pairs";
+                                return Err(syn::Error::new(ident.span(), msg));
+                            }
+                            Some(types::Type::Tuple)
+                                if derives.iter().any(|(t, _)| *t == types::Type::Fallback) =>
+                            {
+                                let msg = "cannot derive both `Fallback` and `Tuple`, which are both implemented for pairs";
+                                return Err(syn::Error::new(ident.span(), msg));
+                            }
+                            Some(d) => derives.push((d, options)),
+                            None => return Err(types::Type::unknown(&ident)),
+                        }
                     }
                 }
-            }
-            syn::Meta::NameValue(ref n) if n.path.to_token_stream().to_string() == "default" => {
-                match n.value {
-                    syn::Expr::Lit(ref lit) => {
-                        if let syn::Lit::Str(ref s) = lit.lit {
-                            match syn::parse_str(&s.value()) {
-                                Ok(path) if default.is_none() => {
-                                    default = Some(path);
-                                }
-                                Ok(_) => {
-                                    return Err(syn::Error::new(
-                                        s.span(),
-                                        "duplicate default module given",
-                                    ))
-                                }
-                                Err(_) => {
-                                    return Err(syn::Error::new(
-                                        s.span(),
-                                        "expected module identifier",
-                                    ))
+                syn::Meta::NameValue(ref n)
+                    if n.path.to_token_stream().to_string() == "default" =>
+                {
+                    match n.value {
+                        syn::Expr::Lit(ref lit) => {
+                            if let syn::Lit::Str(ref s) = lit.lit {
+                                match syn::parse_str(&s.value()) {
+                                    Ok(path) if default.is_none() => {
+                                        default = Some(default::DefaultModule::new(Some(path)));
+                                    }
+                                    Ok(_) => {
+                                        return Err(syn::Error::new(
+                                            s.span(),
+                                            "duplicate default module given",
+                                        ))
+                                    }
+                                    Err(_) => {
+                                        return Err(syn::Error::new(
+                                            s.span(),
+                                            "expected module identifier",
+                                        ))
+                                    }
                                 }
+                            } else {
+                                return Err(syn::Error::new(
+                                    lit.lit.span(),
+                                    "expected string literal",
+                                ));
+                            }
+                        }
+                        syn::Expr::Path(ref expr) => {
+                            let d = default::DefaultModule::new(Some(expr.path.clone()));
+                            if default.replace(d).is_some() {
+                                return Err(syn::Error::new(
+                                    n.span(),
+                                    "duplicate default module given",
+                                ));
+                            }
+                        }
+                        _ => {
+                            return Err(syn::Error::new(
+                                n.value.span(),
+                                "expected path or string literal",
+                            ));
+                        }
+                    }
+                }
+                syn::Meta::List(ref l) if l.path.to_token_stream().to_string() == "default" => {
+                    if default.replace(l.parse_args()?).is_some() {
+                        return Err(syn::Error::new(l.span(), "duplicate default module given"));
+                    }
+                }
+                syn::Meta::List(ref l) if l.path.to_token_stream().to_string() == "variant" => {
+                    if variant.replace(l.parse_args()?).is_some() {
+                        return Err(syn::Error::new(l.span(), "duplicate variant given"));
+                    }
+                }
+                syn::Meta::NameValue(ref n)
+                    if n.path.to_token_stream().to_string() == "dyn_compatible" =>
+                {
+                    let ident = match n.value {
+                        syn::Expr::Path(ref expr) => expr.path.get_ident().cloned(),
+                        _ => None,
+                    };
+                    match ident {
+                        Some(ident) => {
+                            let d = dyn_compatible::DynCompatible { ident };
+                            if dyn_compatible.replace(d).is_some() {
+                                return Err(syn::Error::new(
+                                    n.span(),
+                                    "duplicate dyn_compatible trait given",
+                                ));
+                            }
+                        }
+                        None => {
+                            return Err(syn::Error::new(
+                                n.value.span(),
+                                "expected trait identifier",
+                            ));
+                        }
+                    }
+                }
+                syn::Meta::NameValue(ref n)
+                    if n.path.to_token_stream().to_string() == "extract_defaults" =>
+                {
+                    let ident = match n.value {
+                        syn::Expr::Path(ref expr) => expr.path.get_ident().cloned(),
+                        _ => None,
+                    };
+                    match ident {
+                        Some(ident) => {
+                            if extract_defaults.replace(ident).is_some() {
+                                return Err(syn::Error::new(
+                                    n.span(),
+                                    "duplicate extract_defaults module given",
+                                ));
+                            }
+                        }
+                        None => {
+                            return Err(syn::Error::new(
+                                n.value.span(),
+                                "expected module identifier",
+                            ));
+                        }
+                    }
+                }
+                syn::Meta::NameValue(ref n)
+                    if n.path.to_token_stream().to_string() == "mut_variant" =>
+                {
+                    let ident = match n.value {
+                        syn::Expr::Path(ref expr) => expr.path.get_ident().cloned(),
+                        _ => None,
+                    };
+                    match ident {
+                        Some(ident) => {
+                            let v = mut_variant::MutVariant { ident };
+                            if mut_variant.replace(v).is_some() {
+                                return Err(syn::Error::new(
+                                    n.span(),
+                                    "duplicate mut_variant trait given",
+                                ));
                             }
-                        } else {
-                            return Err(syn::Error::new(lit.lit.span(), "expected string literal"));
                         }
+                        None => {
+                            return Err(syn::Error::new(
+                                n.value.span(),
+                                "expected trait identifier",
+                            ));
+                        }
+                    }
+                }
+                syn::Meta::NameValue(ref n) if n.path.to_token_stream().to_string() == "either" => {
+                    let e = either::Either::from_expr(&n.value)?;
+                    if either.replace(e).is_some() {
+                        return Err(syn::Error::new(n.span(), "duplicate dispatch enum given"));
                     }
-                    syn::Expr::Path(ref expr) => {
-                        if default.replace(expr.path.clone()).is_some() {
+                }
+                syn::Meta::NameValue(ref n)
+                    if n.path.to_token_stream().to_string() == "enum_dispatch" =>
+                {
+                    let e = either::Either::from_expr(&n.value)?;
+                    if e.types.is_none() {
+                        let msg = "expected a list of types, such as `Codecs(Gzip, Zstd)`";
+                        return Err(syn::Error::new(n.value.span(), msg));
+                    }
+                    if either.replace(e).is_some() {
+                        return Err(syn::Error::new(n.span(), "duplicate dispatch enum given"));
+                    }
+                }
+                syn::Meta::NameValue(ref n)
+                    if n.path.to_token_stream().to_string() == "generic" =>
+                {
+                    let ident = match n.value {
+                        syn::Expr::Path(ref expr) => expr.path.get_ident().cloned(),
+                        _ => None,
+                    };
+                    match ident {
+                        Some(ident) => {
+                            if options.generic_type.replace(ident).is_some() {
+                                return Err(syn::Error::new(
+                                    n.span(),
+                                    "duplicate generic type given",
+                                ));
+                            }
+                        }
+                        None => {
                             return Err(syn::Error::new(
-                                n.span(),
-                                "duplicate default module given",
+                                n.value.span(),
+                                "expected generic type identifier",
                             ));
                         }
                     }
-                    _ => {
+                }
+                syn::Meta::List(ref l)
+                    if l.path.to_token_stream().to_string() == "forward_attrs" =>
+                {
+                    let idents = l.parse_args_with(
+                        Punctuated::<syn::Ident, syn::Token![,]>::parse_terminated,
+                    )?;
+                    if options
+                        .forward_attrs
+                        .replace(idents.into_iter().collect())
+                        .is_some()
+                    {
+                        return Err(syn::Error::new(l.span(), "duplicate forward_attrs given"));
+                    }
+                }
+                syn::Meta::Path(ref p) if p.to_token_stream().to_string() == "default" => {
+                    if default.replace(default::DefaultModule::new(None)).is_some() {
+                        return Err(syn::Error::new(p.span(), "duplicate default module given"));
+                    }
+                }
+                syn::Meta::Path(_) | syn::Meta::List(_) if arg.path().is_ident("boxed_futures") => {
+                    if boxed_futures
+                        .replace(boxed::BoxedFutures::from_meta(arg)?)
+                        .is_some()
+                    {
+                        return Err(syn::Error::new(arg.span(), "duplicate boxed_futures given"));
+                    }
+                }
+                syn::Meta::Path(ref p)
+                    if p.to_token_stream().to_string() == "assert_dyn_compatible" =>
+                {
+                    if assert_dyn_compatible {
                         return Err(syn::Error::new(
-                            n.value.span(),
-                            "expected path or string literal",
+                            p.span(),
+                            "duplicate assert_dyn_compatible given",
                         ));
                     }
+                    assert_dyn_compatible = true;
                 }
+                _ => return Err(syn::Error::new(arg.span(), "unexpected argument")),
+            }
+        }
+
+        // the default implementations cannot be both extracted from the
+        // trait and deferred to another module
+        if let (Some(_), Some(ref ident)) = (&default, &extract_defaults) {
+            return Err(syn::Error::new(
+                ident.span(),
+                "extract_defaults cannot be used with a default module",
+            ));
+        }
+
+        // the generic type and forwarded attributes apply to all derives
+        for (_, d) in derives.iter_mut() {
+            d.generic_type = options.generic_type.clone();
+            d.forward_attrs = options.forward_attrs.clone();
+        }
+
+        Ok(Self {
+            assert_dyn_compatible,
+            boxed_futures,
+            default,
+            derives,
+            dyn_compatible,
+            either,
+            extract_defaults,
+            mut_variant,
+            options,
+            variant,
+        })
+    }
+}
+
+// ---------------------------------------------------------------------------
+
+/// Add the default implementations of the trait methods.
+///
+/// The provided method bodies are first moved to the `extract` module if
+/// given, then the methods without a body are deferred to the `default`
+/// module or to their `default_fn`, asserting the signature of the default
+/// functions.
+fn provide_defaults(
+    trait_: syn::ItemTrait,
+    extract: Option<syn::Ident>,
+    default: Option<&default::DefaultModule>,
+    method_args: &[(syn::Ident, method::MethodArgs)],
+    options: &derive::Options,
+    out: &mut proc_macro2::TokenStream,
+) -> syn::ItemTrait {
+    let trait_ = match extract {
+        None => trait_,
+        Some(ref module) => match extract::extract_defaults(trait_.clone(), module, options) {
+            Ok((trait_, module)) => {
+                out.extend(quote!(#module));
+                trait_
+            }
+            Err(err) => {
+                out.extend(err.to_compile_error());
+                trait_
+            }
+        },
+    };
+    if default.is_none() && method_args.iter().all(|(_, m)| m.default_fn.is_none()) {
+        return trait_;
+    }
+    match default::defer_trait_methods(trait_.clone(), default, method_args) {
+        Ok((trait_, deferred)) => {
+            for assertion in default::assert_signatures(&trait_, &deferred) {
+                out.extend(quote!(#assertion));
             }
-            _ => return Err(syn::Error::new(arg.span(), "unexpected argument")),
+            trait_
         }
+        Err(err) => {
+            out.extend(err.to_compile_error());
+            trait_
+        }
+    }
+}
 
-        Ok(Self { default, derives })
+/// Derive the trait for the given wrapper types.
+///
+/// All errors are reported at once, and the first one also lists the derives
+/// supported by the trait.
+fn derive_trait(
+    trait_: &syn::ItemTrait,
+    derives: &[(types::Type, derive::Options)],
+    out: &mut proc_macro2::TokenStream,
+) {
+    let mut errors: Option<syn::Error> = None;
+    for (d, options) in derives {
+        match d.defer_trait_methods(trait_, options) {
+            Ok(items) => out.extend(quote!(#(#items)*)),
+            Err(e) => match errors {
+                Some(ref mut err) => err.combine(e),
+                None => errors = Some(e),
+            },
+        }
+    }
+    if let Some(err) = errors {
+        let mut errors = err.into_iter();
+        if let Some(first) = errors.next() {
+            let msg = format!(
+                "{}; {}",
+                first,
+                types::Type::supported_note(trait_, derives)
+            );
+            let mut err = syn::Error::new(first.span(), msg);
+            errors.for_each(|e| err.combine(e));
+            out.extend(err.to_compile_error());
+        }
+    }
+}
+
+/// Rewrite the `async fn` definitions of an impl block to return boxed futures.
+///
+/// Only the `boxed_futures` argument is supported on impl blocks, so that
+/// implementors of a trait with boxed futures can write `async fn` methods.
+fn blanket_impl(
+    args: &Punctuated<syn::Meta, Token![,]>,
+    impl_: syn::ItemImpl,
+) -> proc_macro2::TokenStream {
+    let mut boxed = None;
+    let mut errors: Option<syn::Error> = None;
+    for arg in args.iter() {
+        let result = if !arg.path().is_ident("boxed_futures") {
+            let msg = "only `boxed_futures` can be given for an impl block";
+            Err(syn::Error::new(arg.span(), msg))
+        } else if boxed.is_some() {
+            Err(syn::Error::new(arg.span(), "duplicate boxed_futures given"))
+        } else {
+            boxed::BoxedFutures::from_meta(arg).map(|b| boxed = Some(b))
+        };
+        if let Err(e) = result {
+            match errors {
+                Some(ref mut err) => err.combine(e),
+                None => errors = Some(e),
+            }
+        }
+    }
+    let err = errors.map(|e| e.to_compile_error());
+    match boxed {
+        Some(boxed) => {
+            let impl_ = boxed.box_impl_futures(impl_);
+            quote!(#err #impl_)
+        }
+        None if err.is_some() => quote!(#err #impl_),
+        None => {
+            let msg = "expected a `boxed_futures` argument for an impl block";
+            let err = syn::Error::new(impl_.span(), msg).to_compile_error();
+            quote!(#err #impl_)
+        }
     }
 }
 
@@ -106,34 +484,250 @@ pub fn blanket(
     input: proc_macro::TokenStream,
 ) -> proc_macro::TokenStream {
     // parse input
-    let trait_ = parse_macro_input!(input as syn::ItemTrait);
-    let args = parse_macro_input!(args as syn::Meta);
+    let args = parse_macro_input!(args with Punctuated::<syn::Meta, Token![,]>::parse_terminated);
+    let mut trait_ = match parse_macro_input!(input as syn::Item) {
+        syn::Item::Trait(trait_) => trait_,
+        syn::Item::Impl(impl_) => return proc_macro::TokenStream::from(blanket_impl(&args, impl_)),
+        item => {
+            let msg = "expected a trait, or an impl block with a `boxed_futures` argument";
+            let err = syn::Error::new(item.span(), msg).to_compile_error();
+            return proc_macro::TokenStream::from(quote!(#err #item));
+        }
+    };
+    // generate output
+    let mut out = proc_macro2::TokenStream::new();
+    // extract the `#[blanket(...)]` arguments of the trait methods
+    let method_args = match method::take_method_args(&mut trait_) {
+        Ok(method_args) => method_args,
+        Err(e) => {
+            out.extend(e.to_compile_error());
+            Vec::new()
+        }
+    };
     // parse macro arguments and immediately exit if they are invalid
-    let args = match Args::from_meta(&args) {
+    let mut args = match Args::from_metas(&args) {
         Ok(args) => args,
         Err(e) => {
             let err = e.to_compile_error();
-            return proc_macro::TokenStream::from(quote!(#err #trait_));
+            out.extend(quote!(#err #trait_));
+            return proc_macro::TokenStream::from(out);
         }
     };
-    // generate output
-    let mut out = proc_macro2::TokenStream::new();
-    // update trait methods declaration if given a `default = "..."` argument,
-    // otherwise simply keep the output
-    match args.default {
-        None => out.extend(quote!(#trait_)),
-        Some(d) => match default::defer_trait_methods(trait_.clone(), d) {
-            Ok(trait_) => out.extend(quote!(#trait_)),
-            Err(err) => out.extend(err.to_compile_error()),
+    // give each derive the functions overriding the trait methods, the
+    // strategies combining their results for the fan-out derives, and the
+    // values returned on `None` for the `Option` derive
+    for (method, m_args) in method_args.iter() {
+        for (t, path) in m_args.overrides.iter() {
+            match args.derives.iter_mut().find(|(d, _)| d == t) {
+                Some((_, options)) => options.overrides.push((method.clone(), path.clone())),
+                None => {
+                    let msg = format!(
+                        "cannot override a method for `{}` without deriving it",
+                        t.name()
+                    );
+                    out.extend(syn::Error::new(path.span(), msg).to_compile_error());
+                }
+            }
+        }
+        if let Some(combine) = m_args.combine {
+            let mut fan_out = args
+                .derives
+                .iter_mut()
+                .filter(|(d, _)| d.is_fan_out())
+                .peekable();
+            if fan_out.peek().is_none() {
+                let msg = "cannot combine the results of a method without deriving a fan-out wrapper such as `Vec`";
+                out.extend(syn::Error::new(method.span(), msg).to_compile_error());
+            }
+            for (_, options) in fan_out {
+                options.combine.push((method.clone(), combine));
+            }
+        }
+        if let Some(ref expr) = m_args.on_none {
+            match args
+                .derives
+                .iter_mut()
+                .find(|(d, _)| *d == types::Type::Option)
+            {
+                Some((_, options)) => options.on_none.push((method.clone(), expr.clone())),
+                None => {
+                    let msg = "cannot give a value for `None` without deriving `Option`";
+                    out.extend(syn::Error::new(expr.span(), msg).to_compile_error());
+                }
+            }
+        }
+    }
+    // generate the mutable twin of the trait if given a `mut_variant = ...`
+    // argument, before any default implementation is added to the trait
+    let twin = match args.mut_variant {
+        Some(ref v) => Some(v.make_trait(&trait_, &method_args)),
+        None => {
+            for (method, m_args) in method_args.iter() {
+                if !m_args.mut_args.is_empty() {
+                    let msg = "`#[blanket(mut)]` arguments require a `mut_variant = ...` argument";
+                    out.extend(syn::Error::new(method.span(), msg).to_compile_error());
+                }
+            }
+            None
+        }
+    };
+    let mut trait_ = provide_defaults(
+        trait_,
+        args.extract_defaults.clone(),
+        args.default.as_ref(),
+        &method_args,
+        &args.options,
+        &mut out,
+    );
+    // rewrite `async fn` declarations to return boxed futures if given a
+    // `boxed_futures` argument, so that the trait is dyn-compatible
+    if let Some(ref boxed) = args.boxed_futures {
+        if let Err(e) = boxed.check_trait_defaults(&trait_) {
+            out.extend(e.to_compile_error());
+        }
+        trait_ = boxed.box_trait_futures(trait_);
+    }
+    // check that the trait can be used as a trait object if given an
+    // `assert_dyn_compatible` argument
+    if args.assert_dyn_compatible {
+        match dyn_compatible::assert_dyn_compatible(&trait_) {
+            Ok(item) => out.extend(quote!(#item)),
+            Err(e) => out.extend(e.to_compile_error()),
+        }
+    }
+    // generate the variant trait if given a `variant(...)` argument, and
+    // derive the original trait for all implementors of the variant: the
+    // wrapper types are then derived for the variant, since deriving them
+    // for the original trait would conflict with that blanket implementation
+    let derived = match args.variant {
+        None => {
+            out.extend(quote!(#trait_));
+            trait_.clone()
+        }
+        Some(v) => match v.make_trait(&trait_) {
+            Ok(variant) => {
+                trait_
+                    .attrs
+                    .push(syn::parse_quote!(#[allow(async_fn_in_trait)]));
+                out.extend(quote!(#trait_ #variant));
+                match v.derive_original(&trait_, &variant, &args.options) {
+                    Ok(item) => out.extend(quote!(#item)),
+                    Err(e) => out.extend(e.to_compile_error()),
+                }
+                // the wrappers sharing their value need the wrapped type to
+                // be `Sync` to satisfy the bounds of the variant
+                let derives = std::mem::take(&mut args.derives);
+                for (d, mut options) in derives {
+                    let generic_type = options.generic_type(&variant);
+                    match v.wrapper_predicates(&d, &generic_type) {
+                        Ok(predicates) => {
+                            options.predicates.extend(predicates);
+                            args.derives.push((d, options));
+                        }
+                        Err(e) => out.extend(e.to_compile_error()),
+                    }
+                }
+                variant
+            }
+            Err(e) => {
+                out.extend(e.to_compile_error());
+                out.extend(quote!(#trait_));
+                trait_.clone()
+            }
         },
     };
+    // generate the dyn-compatible companion trait if given a
+    // `dyn_compatible = ...` argument
+    if let Some(d) = args.dyn_compatible {
+        match d.derive(&trait_, &args.options) {
+            Ok(items) => out.extend(quote!(#(#items)*)),
+            Err(e) => out.extend(e.to_compile_error()),
+        }
+    }
     // add derived implementations
-    for d in args.derives {
-        match d.defer_trait_methods(&trait_) {
-            Ok(item) => out.extend(quote!(#item)),
+    derive_trait(&derived, &args.derives, &mut out);
+    // generate the enum dispatching to one of several implementors if
+    // given an `either = ...` or `enum_dispatch = ...` argument
+    if let Some(e) = args.either {
+        match e.derive(&derived, &args.options) {
+            Ok(items) => out.extend(quote!(#(#items)*)),
             Err(e) => out.extend(e.to_compile_error()),
         }
     }
+    // add the mutable twin of the trait, with its own default module and
+    // derived implementations, but without the per-method functions which
+    // were written for the original trait
+    if let Some(twin) = twin {
+        let twin_args = method_args
+            .iter()
+            .map(|(method, m_args)| {
+                let m_args = method::MethodArgs {
+                    no_default: m_args.no_default,
+                    ..Default::default()
+                };
+                (method.clone(), m_args)
+            })
+            .collect::<Vec<_>>();
+        let twin_default = args.default.as_ref().map(|d| default::DefaultModule {
+            module: None,
+            prefix: d.prefix.clone(),
+            partial: d.partial,
+        });
+        let twin_extract = args
+            .extract_defaults
+            .as_ref()
+            .map(|_| utils::to_snake_case(&twin.ident));
+        let mut twin = provide_defaults(
+            twin,
+            twin_extract,
+            twin_default.as_ref(),
+            &twin_args,
+            &args.options,
+            &mut out,
+        );
+        if let Some(ref boxed) = args.boxed_futures {
+            if let Err(e) = boxed.check_trait_defaults(&twin) {
+                out.extend(e.to_compile_error());
+            }
+            twin = boxed.box_trait_futures(twin);
+        }
+        out.extend(quote!(#twin));
+        let derives = args
+            .derives
+            .iter()
+            .map(|(d, options)| {
+                let mut options = options.clone();
+                options.overrides.clear();
+                (*d, options)
+            })
+            .collect::<Vec<_>>();
+        derive_trait(&twin, &derives, &mut out);
+    }
     // // return the new `proc-macro2` token stream as a `proc-macro` stream
     proc_macro::TokenStream::from(out)
 }
+
+/// Generate a visitor trait for the types of a module.
+///
+/// This procedural macro must be used on a `mod` block with inline content.
+/// It generates a trait with one `visit_<type>` method for each `struct` and
+/// `enum` of the module, and a default module with functions visiting the
+/// fields of each type.
+///
+#[proc_macro_attribute]
+pub fn visitor(
+    args: proc_macro::TokenStream,
+    input: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    // parse input
+    let module = parse_macro_input!(input as syn::ItemMod);
+    let args = parse_macro_input!(args as visitor::VisitorArgs);
+    // generate output
+    match visitor::derive_visitor(module.clone(), &args) {
+        Ok(module) => proc_macro::TokenStream::from(quote!(#module)),
+        Err(e) => {
+            let err = e.to_compile_error();
+            proc_macro::TokenStream::from(quote!(#err #module))
+        }
+    }
+}
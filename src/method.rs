@@ -0,0 +1,274 @@
+use syn::ext::IdentExt;
+use syn::parse::ParseStream;
+use syn::punctuated::Punctuated;
+
+use crate::fanout::Combine;
+use crate::types;
+
+/// Arguments given to a trait method with a `#[blanket(...)]` attribute.
+#[derive(Default)]
+pub struct MethodArgs {
+    /// Functions implementing the method for a given derive, instead of
+    /// forwarding the call to the wrapped type.
+    pub overrides: Vec<(types::Type, syn::Path)>,
+    /// The function providing the default implementation of the method.
+    pub default_fn: Option<syn::Path>,
+    /// Whether the method is kept without a default implementation.
+    pub no_default: bool,
+    /// The arguments marked with `#[blanket(mut)]`, taken by mutable
+    /// reference in the mutable variant of the trait.
+    pub mut_args: Vec<syn::Ident>,
+    /// The strategy combining the results of the values called by the
+    /// fan-out wrapper types.
+    pub combine: Option<Combine>,
+    /// The value returned by the `Option` wrapper type when called on `None`.
+    pub on_none: Option<syn::Expr>,
+}
+
+impl MethodArgs {
+    /// Parse a single `#[blanket(...)]` attribute into the method arguments.
+    fn parse_attribute(&mut self, attr: &syn::Attribute) -> syn::Result<()> {
+        attr.parse_args_with(|input: ParseStream| {
+            while !input.is_empty() {
+                // `override` is a reserved keyword, so arguments are parsed
+                // as raw identifiers
+                let key = input.call(syn::Ident::parse_any)?;
+                match key.to_string().as_str() {
+                    "override" => {
+                        let content;
+                        syn::parenthesized!(content in input);
+                        let pairs = Punctuated::<_, syn::Token![,]>::parse_terminated_with(
+                            &content,
+                            |input: ParseStream| {
+                                let ident = input.parse::<syn::Ident>()?;
+                                input.parse::<syn::Token![=]>()?;
+                                Ok((ident, input.parse::<syn::Path>()?))
+                            },
+                        )?;
+                        for (ident, path) in pairs {
+                            let t = types::Type::from_str(&ident.to_string())
+                                .ok_or_else(|| types::Type::unknown(&ident))?;
+                            if self.overrides.iter().any(|(o, _)| *o == t) {
+                                let msg = format!("duplicate override for `{}` given", t.name());
+                                return Err(syn::Error::new(ident.span(), msg));
+                            }
+                            self.overrides.push((t, path));
+                        }
+                    }
+                    "default_fn" => {
+                        input.parse::<syn::Token![=]>()?;
+                        if self.default_fn.replace(input.parse()?).is_some() {
+                            let msg = "duplicate default_fn given";
+                            return Err(syn::Error::new(key.span(), msg));
+                        }
+                    }
+                    "combine" => {
+                        input.parse::<syn::Token![=]>()?;
+                        // `try` is a reserved keyword as well
+                        let value = input.call(syn::Ident::parse_any)?;
+                        let combine = Combine::from_str(&value.to_string()).ok_or_else(|| {
+                            syn::Error::new(
+                                value.span(),
+                                "expected one of `all`, `any`, `default` or `try`",
+                            )
+                        })?;
+                        if self.combine.replace(combine).is_some() {
+                            let msg = "duplicate combine given";
+                            return Err(syn::Error::new(key.span(), msg));
+                        }
+                    }
+                    "on_none" => {
+                        input.parse::<syn::Token![=]>()?;
+                        if self.on_none.replace(input.parse()?).is_some() {
+                            let msg = "duplicate on_none given";
+                            return Err(syn::Error::new(key.span(), msg));
+                        }
+                    }
+                    "no_default" => {
+                        if self.no_default {
+                            let msg = "duplicate no_default given";
+                            return Err(syn::Error::new(key.span(), msg));
+                        }
+                        self.no_default = true;
+                    }
+                    _ => {
+                        return Err(syn::Error::new(key.span(), "unexpected method argument"));
+                    }
+                }
+                if !input.is_empty() {
+                    input.parse::<syn::Token![,]>()?;
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// Parse the `#[blanket(mut)]` attributes of a single method argument.
+    fn parse_argument(&mut self, arg: &syn::PatType) -> syn::Result<()> {
+        for attr in arg
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("blanket"))
+        {
+            attr.parse_args_with(|input: ParseStream| input.parse::<syn::Token![mut]>())?;
+        }
+        let ident = match (&*arg.pat, &*arg.ty) {
+            (syn::Pat::Ident(p), syn::Type::Reference(r)) if r.mutability.is_none() => &p.ident,
+            _ => {
+                let msg = "expected an identifier with a shared reference type";
+                return Err(syn::Error::new_spanned(arg, msg));
+            }
+        };
+        if self.mut_args.contains(ident) {
+            return Err(syn::Error::new(ident.span(), "duplicate mut given"));
+        }
+        self.mut_args.push(ident.clone());
+        Ok(())
+    }
+}
+
+/// Remove the `#[blanket(...)]` attributes from the methods of `trait_`.
+///
+/// The attributes of the methods and of their arguments are parsed into the
+/// arguments of each method, and all the errors are reported at once. The
+/// attributes are removed even on errors, so that the trait can still be
+/// emitted.
+pub fn take_method_args(trait_: &mut syn::ItemTrait) -> syn::Result<Vec<(syn::Ident, MethodArgs)>> {
+    let mut methods = Vec::new();
+    let mut errors: Option<syn::Error> = None;
+    for item in trait_.items.iter_mut() {
+        if let syn::TraitItem::Fn(ref mut m) = item {
+            let mut args = MethodArgs::default();
+            let mut found = false;
+            for attr in m
+                .attrs
+                .iter()
+                .filter(|attr| attr.path().is_ident("blanket"))
+            {
+                found = true;
+                if let Err(e) = args.parse_attribute(attr) {
+                    match errors {
+                        Some(ref mut err) => err.combine(e),
+                        None => errors = Some(e),
+                    }
+                }
+            }
+            for input in m.sig.inputs.iter_mut() {
+                if let syn::FnArg::Typed(ref mut t) = input {
+                    if !t.attrs.iter().any(|attr| attr.path().is_ident("blanket")) {
+                        continue;
+                    }
+                    found = true;
+                    if let Err(e) = args.parse_argument(t) {
+                        match errors {
+                            Some(ref mut err) => err.combine(e),
+                            None => errors = Some(e),
+                        }
+                    }
+                    t.attrs.retain(|attr| !attr.path().is_ident("blanket"));
+                }
+            }
+            if args.no_default && args.default_fn.is_some() {
+                let msg = "`default_fn` and `no_default` cannot be used together";
+                let e = syn::Error::new(m.sig.ident.span(), msg);
+                match errors {
+                    Some(ref mut err) => err.combine(e),
+                    None => errors = Some(e),
+                }
+            }
+            if found {
+                m.attrs.retain(|attr| !attr.path().is_ident("blanket"));
+                methods.push((m.sig.ident.clone(), args));
+            }
+        }
+    }
+    match errors {
+        Some(err) => Err(err),
+        None => Ok(methods),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use syn::parse_quote;
+
+    use crate::fanout::Combine;
+    use crate::types;
+
+    #[test]
+    fn take_method_args() {
+        let mut trait_: syn::ItemTrait = parse_quote!(
+            trait Trait {
+                /// Get the identifier.
+                #[blanket(override(Arc = crate::arc_id, Rc = rc_id))]
+                fn id(&self) -> usize;
+                #[blanket(default_fn = names::name)]
+                fn name(&self) -> &str;
+                fn visit(&self, #[blanket(mut)] node: &Node);
+                #[blanket(combine = try, on_none = Ok(()))]
+                fn flush(&self) -> Result<(), Error>;
+            }
+        );
+        let args = super::take_method_args(&mut trait_).unwrap();
+        assert_eq!(args.len(), 4);
+        assert_eq!(args[0].0, "id");
+        assert_eq!(args[0].1.overrides.len(), 2);
+        assert_eq!(args[0].1.overrides[0].0, types::Type::Arc);
+        assert_eq!(args[0].1.overrides[1].0, types::Type::Rc);
+        assert!(args[0].1.default_fn.is_none());
+        assert_eq!(args[1].0, "name");
+        assert_eq!(args[1].1.default_fn, Some(syn::parse_quote!(names::name)));
+        assert_eq!(args[2].0, "visit");
+        assert_eq!(
+            args[2].1.mut_args,
+            vec![syn::Ident::new("node", proc_macro2::Span::call_site())]
+        );
+        assert_eq!(args[3].0, "flush");
+        assert_eq!(args[3].1.combine, Some(Combine::Try));
+        assert_eq!(args[3].1.on_none, Some(parse_quote!(Ok(()))));
+        assert_eq!(
+            trait_,
+            parse_quote!(
+                trait Trait {
+                    /// Get the identifier.
+                    fn id(&self) -> usize;
+                    fn name(&self) -> &str;
+                    fn visit(&self, node: &Node);
+                    fn flush(&self) -> Result<(), Error>;
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn take_method_args_errors() {
+        let mut trait_: syn::ItemTrait = parse_quote!(
+            trait Trait {
+                #[blanket(override(Arcc = arc_id))]
+                fn id(&self) -> usize;
+                #[blanket(forward)]
+                fn name(&self) -> &str;
+                #[blanket(no_default, default_fn = names::len)]
+                fn len(&self) -> usize;
+                fn visit(&self, #[blanket(mut)] node: Node);
+                #[blanket(combine = sum)]
+                fn total(&self) -> usize;
+            }
+        );
+        let err = super::take_method_args(&mut trait_).err().unwrap();
+        assert_eq!(err.into_iter().count(), 5);
+        assert_eq!(
+            trait_,
+            parse_quote!(
+                trait Trait {
+                    fn id(&self) -> usize;
+                    fn name(&self) -> &str;
+                    fn len(&self) -> usize;
+                    fn visit(&self, node: Node);
+                    fn total(&self) -> usize;
+                }
+            )
+        );
+    }
+}
@@ -0,0 +1,105 @@
+use syn::parse_quote;
+
+use crate::method::MethodArgs;
+
+/// A twin of a trait taking its receivers and marked arguments mutably.
+///
+/// Given `VisitMut`, generate a trait `VisitMut` where every `&self`
+/// receiver becomes `&mut self`, and every argument marked with
+/// `#[blanket(mut)]` becomes a mutable reference, similarly to the
+/// `Visit` and `VisitMut` traits of `syn`.
+pub struct MutVariant {
+    pub ident: syn::Ident,
+}
+
+impl MutVariant {
+    /// Generate the twin trait declaration from the original trait.
+    pub fn make_trait(
+        &self,
+        trait_: &syn::ItemTrait,
+        method_args: &[(syn::Ident, MethodArgs)],
+    ) -> syn::ItemTrait {
+        let mut twin = trait_.clone();
+        twin.ident = self.ident.clone();
+
+        for item in twin.items.iter_mut() {
+            if let syn::TraitItem::Fn(ref mut m) = item {
+                let mut_args = method_args
+                    .iter()
+                    .find(|(ident, _)| *ident == m.sig.ident)
+                    .map(|(_, args)| args.mut_args.as_slice())
+                    .unwrap_or_default();
+                for arg in m.sig.inputs.iter_mut() {
+                    match arg {
+                        syn::FnArg::Receiver(ref mut r)
+                            if r.colon_token.is_none()
+                                && r.reference.is_some()
+                                && r.mutability.is_none() =>
+                        {
+                            r.mutability = Some(parse_quote!(mut));
+                            if let syn::Type::Reference(ref mut ty) = *r.ty {
+                                ty.mutability = Some(parse_quote!(mut));
+                            }
+                        }
+                        syn::FnArg::Typed(ref mut t) => {
+                            let marked = match *t.pat {
+                                syn::Pat::Ident(ref p) => mut_args.contains(&p.ident),
+                                _ => false,
+                            };
+                            if let (true, syn::Type::Reference(ref mut ty)) = (marked, &mut *t.ty) {
+                                ty.mutability = Some(parse_quote!(mut));
+                            }
+                        }
+                        _ => (),
+                    }
+                }
+            }
+        }
+
+        twin
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use syn::parse_quote;
+
+    use super::MutVariant;
+    use crate::method::MethodArgs;
+
+    #[test]
+    fn make_trait() {
+        let trait_ = parse_quote!(
+            /// Visit an expression.
+            pub trait Visit {
+                fn visit_expr(&self, expr: &Expr, depth: &usize);
+                fn visit_lit(&mut self, lit: &Lit);
+                fn name(&'static self) -> &'static str;
+                fn new() -> Self;
+            }
+        );
+        let method_args = [(
+            parse_quote!(visit_expr),
+            MethodArgs {
+                mut_args: vec![parse_quote!(expr)],
+                ..Default::default()
+            },
+        )];
+        let variant = MutVariant {
+            ident: parse_quote!(VisitMut),
+        };
+        assert_eq!(
+            variant.make_trait(&trait_, &method_args),
+            parse_quote!(
+                /// Visit an expression.
+                pub trait VisitMut {
+                    fn visit_expr(&mut self, expr: &mut Expr, depth: &usize);
+                    fn visit_lit(&mut self, lit: &Lit);
+                    fn name(&'static mut self) -> &'static str;
+                    fn new() -> Self;
+                }
+            )
+        );
+    }
+}
@@ -1,5 +1,6 @@
 use syn::parse_quote;
 
+use crate::derive::Options;
 use crate::derive::Receiver;
 use crate::derive::WrapperType;
 
@@ -13,8 +14,12 @@ impl WrapperType for ArcType {
     }
 }
 
-pub fn derive(trait_: &syn::ItemTrait) -> syn::Result<syn::ItemImpl> {
-    ArcType::derive(trait_)
+pub fn derive(trait_: &syn::ItemTrait, options: &Options) -> syn::Result<syn::ItemImpl> {
+    ArcType::derive(trait_, options)
+}
+
+pub fn supports(trait_: &syn::ItemTrait) -> bool {
+    ArcType::supports(trait_)
 }
 
 #[cfg(test)]
@@ -29,7 +34,7 @@ mod tests {
                 trait Trait {}
             );
             assert_eq!(
-                super::super::derive(&trait_).unwrap(),
+                super::super::derive(&trait_, &Default::default()).unwrap(),
                 parse_quote!(
                     #[automatically_derived]
                     impl<T: Trait + ?Sized> Trait for std::sync::Arc<T> {}
@@ -45,7 +50,7 @@ mod tests {
                 }
             );
             assert_eq!(
-                super::super::derive(&trait_).unwrap(),
+                super::super::derive(&trait_, &Default::default()).unwrap(),
                 parse_quote!(
                     #[automatically_derived]
                     impl<T: Trait + ?Sized> Trait for std::sync::Arc<T> {
@@ -65,7 +70,7 @@ mod tests {
                     fn my_method(&mut self);
                 }
             );
-            assert!(super::super::derive(&trait_).is_err());
+            assert!(super::super::derive(&trait_, &Default::default()).is_err());
         }
 
         #[test]
@@ -75,7 +80,7 @@ mod tests {
                     fn my_method(self);
                 }
             );
-            assert!(super::super::derive(&trait_).is_err());
+            assert!(super::super::derive(&trait_, &Default::default()).is_err());
         }
 
         #[test]
@@ -85,7 +90,7 @@ mod tests {
                     fn my_method(self: Box<Self>);
                 }
             );
-            assert!(super::super::derive(&trait_).is_err());
+            assert!(super::super::derive(&trait_, &Default::default()).is_err());
         }
 
         #[test]
@@ -93,7 +98,7 @@ mod tests {
             let trait_ = parse_quote!(
                 trait MyTrait<T> {}
             );
-            let derived = super::super::derive(&trait_).unwrap();
+            let derived = super::super::derive(&trait_, &Default::default()).unwrap();
 
             assert_eq!(
                 derived,
@@ -109,7 +114,7 @@ mod tests {
             let trait_ = parse_quote!(
                 trait MyTrait<T: 'static + Send> {}
             );
-            let derived = super::super::derive(&trait_).unwrap();
+            let derived = super::super::derive(&trait_, &Default::default()).unwrap();
 
             assert_eq!(
                 derived,
@@ -125,7 +130,7 @@ mod tests {
             let trait_ = parse_quote!(
                 trait MyTrait<'a, 'b: 'a, T: 'static + Send> {}
             );
-            let derived = super::super::derive(&trait_).unwrap();
+            let derived = super::super::derive(&trait_, &Default::default()).unwrap();
 
             assert_eq!(
                 derived,
@@ -146,7 +151,7 @@ mod tests {
                     type Return;
                 }
             );
-            let derived = super::super::derive(&trait_).unwrap();
+            let derived = super::super::derive(&trait_, &Default::default()).unwrap();
 
             assert_eq!(
                 derived,
@@ -166,7 +171,7 @@ mod tests {
                     type Return: Clone;
                 }
             );
-            let derived = super::super::derive(&trait_).unwrap();
+            let derived = super::super::derive(&trait_, &Default::default()).unwrap();
 
             assert_eq!(
                 derived,
@@ -186,7 +191,7 @@ mod tests {
                     type r#type;
                 }
             );
-            let derived = super::super::derive(&trait_).unwrap();
+            let derived = super::super::derive(&trait_, &Default::default()).unwrap();
 
             assert_eq!(
                 derived,
@@ -209,7 +214,7 @@ mod tests {
                     type Return: Send;
                 }
             );
-            let derived = super::super::derive(&trait_).unwrap();
+            let derived = super::super::derive(&trait_, &Default::default()).unwrap();
 
             assert_eq!(
                 derived,
@@ -232,7 +237,7 @@ mod tests {
                     type Return;
                 }
             );
-            let derived = super::super::derive(&trait_).unwrap();
+            let derived = super::super::derive(&trait_, &Default::default()).unwrap();
 
             assert_eq!(
                 derived,
@@ -252,7 +257,7 @@ mod tests {
                     type Return<T>;
                 }
             );
-            let derived = super::super::derive(&trait_).unwrap();
+            let derived = super::super::derive(&trait_, &Default::default()).unwrap();
 
             assert_eq!(
                 derived,
@@ -272,7 +277,7 @@ mod tests {
                     type Return<T: 'static + Send>;
                 }
             );
-            let derived = super::super::derive(&trait_).unwrap();
+            let derived = super::super::derive(&trait_, &Default::default()).unwrap();
 
             assert_eq!(
                 derived,
@@ -294,16 +299,79 @@ mod tests {
                         Self: 'a;
                 }
             );
-            let derived = super::super::derive(&trait_).unwrap();
+            let derived = super::super::derive(&trait_, &Default::default()).unwrap();
 
             assert_eq!(
                 derived,
                 parse_quote!(
                     #[automatically_derived]
                     impl<MT: MyTrait + ?Sized> MyTrait for std::sync::Arc<MT> {
-                        type Return<'a> = <MT as MyTrait>::Return<'a>
+                        type Return<'a>
+                            = <MT as MyTrait>::Return<'a>
                         where
-                            Self: 'a;
+                            Self: 'a,
+                            MT: 'a;
+                    }
+                )
+            );
+        }
+
+        #[test]
+        #[rustfmt::skip]
+        fn options() {
+            let trait_ = parse_quote!(
+                trait MyTrait {
+                    fn my_method(&self);
+                }
+            );
+            let options = parse_quote!(cfg = feature = "sync", where = MT: Send, MT: 'static, inline = never);
+            assert_eq!(
+                super::super::derive(&trait_, &options).unwrap(),
+                parse_quote!(
+                    #[cfg(feature = "sync")]
+                    #[automatically_derived]
+                    impl<MT: MyTrait + ?Sized> MyTrait for std::sync::Arc<MT>
+                    where
+                        MT: Send,
+                        MT: 'static
+                    {
+                        #[inline(never)]
+                        fn my_method(&self) {
+                            (*(*self)).my_method()
+                        }
+                    }
+                )
+            );
+        }
+
+        #[test]
+        fn overrides() {
+            let trait_ = parse_quote!(
+                trait MyTrait {
+                    fn id(&self) -> usize;
+                    fn my_method(&mut self);
+                }
+            );
+            let options = crate::derive::Options {
+                overrides: vec![
+                    (parse_quote!(id), parse_quote!(crate::arc_id)),
+                    (parse_quote!(my_method), parse_quote!(arc_method)),
+                ],
+                ..Default::default()
+            };
+            assert_eq!(
+                super::super::derive(&trait_, &options).unwrap(),
+                parse_quote!(
+                    #[automatically_derived]
+                    impl<MT: MyTrait + ?Sized> MyTrait for std::sync::Arc<MT> {
+                        #[inline]
+                        fn id(&self) -> usize {
+                            crate::arc_id(self)
+                        }
+                        #[inline]
+                        fn my_method(&mut self) {
+                            arc_method(self)
+                        }
                     }
                 )
             );
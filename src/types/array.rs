@@ -0,0 +1,70 @@
+use syn::parse_quote;
+
+use crate::derive::Options;
+use crate::derive::Receiver;
+use crate::derive::WrapperType;
+use crate::fanout;
+
+struct ArrayType;
+
+impl WrapperType for ArrayType {
+    const NAME: &'static str = "Array";
+    const RECEIVERS: &'static [Receiver] = &[Receiver::Ref, Receiver::Mut, Receiver::Owned];
+    const SIZED: bool = true;
+    fn wrap(ty: &syn::Ident) -> syn::Type {
+        parse_quote!([#ty; N])
+    }
+    fn extra_generics() -> Vec<syn::GenericParam> {
+        vec![parse_quote!(const N: usize)]
+    }
+    fn forward_method(
+        m: &syn::TraitItemFn,
+        _trait_ident: &syn::Ident,
+        _generic_type: &syn::Ident,
+        _trait_generic_names: &syn::Generics,
+        options: &Options,
+    ) -> syn::Result<syn::Block> {
+        fanout::forward_to_elements::<Self>(m, options)
+    }
+}
+
+pub fn derive(trait_: &syn::ItemTrait, options: &Options) -> syn::Result<syn::ItemImpl> {
+    ArrayType::derive(trait_, options)
+}
+
+pub fn supports(trait_: &syn::ItemTrait, options: &Options) -> bool {
+    fanout::supports::<ArrayType>(trait_, options)
+}
+
+#[cfg(test)]
+mod tests {
+    mod derive {
+
+        use syn::parse_quote;
+
+        #[test]
+        fn generics() {
+            let trait_ = parse_quote!(
+                trait Trait<T> {
+                    type Item;
+                    fn notify(&self, value: &T);
+                }
+            );
+            assert_eq!(
+                super::super::derive(&trait_, &Default::default()).unwrap(),
+                parse_quote!(
+                    #[automatically_derived]
+                    impl<T, T_: Trait<T>, const N: usize> Trait<T> for [T_; N] {
+                        type Item = <T_ as Trait<T>>::Item;
+                        #[inline]
+                        fn notify(&self, value: &T) {
+                            for item in self.iter() {
+                                item.notify(value);
+                            }
+                        }
+                    }
+                )
+            );
+        }
+    }
+}
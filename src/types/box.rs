@@ -1,5 +1,6 @@
 use syn::parse_quote;
 
+use crate::derive::Options;
 use crate::derive::Receiver;
 use crate::derive::WrapperType;
 
@@ -13,8 +14,12 @@ impl WrapperType for BoxType {
     }
 }
 
-pub fn derive(trait_: &syn::ItemTrait) -> syn::Result<syn::ItemImpl> {
-    BoxType::derive(trait_)
+pub fn derive(trait_: &syn::ItemTrait, options: &Options) -> syn::Result<syn::ItemImpl> {
+    BoxType::derive(trait_, options)
+}
+
+pub fn supports(trait_: &syn::ItemTrait) -> bool {
+    BoxType::supports(trait_)
 }
 
 #[cfg(test)]
@@ -28,7 +33,7 @@ mod tests {
             let trait_ = parse_quote!(
                 trait MyTrait {}
             );
-            let derived = super::super::derive(&trait_).unwrap();
+            let derived = super::super::derive(&trait_, &Default::default()).unwrap();
             assert_eq!(
                 derived,
                 parse_quote!(
@@ -46,7 +51,7 @@ mod tests {
                 }
             );
             assert_eq!(
-                super::super::derive(&trait_).unwrap(),
+                super::super::derive(&trait_, &Default::default()).unwrap(),
                 parse_quote!(
                     #[automatically_derived]
                     impl<MT: MyTrait + ?Sized> MyTrait for std::boxed::Box<MT> {
@@ -67,7 +72,7 @@ mod tests {
                 }
             );
             assert_eq!(
-                super::super::derive(&trait_).unwrap(),
+                super::super::derive(&trait_, &Default::default()).unwrap(),
                 parse_quote!(
                     #[automatically_derived]
                     impl<MT: MyTrait + ?Sized> MyTrait for std::boxed::Box<MT> {
@@ -88,7 +93,7 @@ mod tests {
                 }
             );
             assert_eq!(
-                super::super::derive(&trait_).unwrap(),
+                super::super::derive(&trait_, &Default::default()).unwrap(),
                 parse_quote!(
                     #[automatically_derived]
                     impl<MT: MyTrait> MyTrait for std::boxed::Box<MT> {
@@ -108,7 +113,7 @@ mod tests {
                     fn my_method(self: std::boxed::Box<Self>);
                 }
             );
-            assert!(super::super::derive(&trait_).is_err());
+            assert!(super::super::derive(&trait_, &Default::default()).is_err());
         }
 
         #[test]
@@ -116,7 +121,7 @@ mod tests {
             let trait_ = parse_quote!(
                 trait MyTrait<T> {}
             );
-            let derived = super::super::derive(&trait_).unwrap();
+            let derived = super::super::derive(&trait_, &Default::default()).unwrap();
 
             assert_eq!(
                 derived,
@@ -132,7 +137,7 @@ mod tests {
             let trait_ = parse_quote!(
                 trait MyTrait<T: 'static + Send> {}
             );
-            let derived = super::super::derive(&trait_).unwrap();
+            let derived = super::super::derive(&trait_, &Default::default()).unwrap();
 
             assert_eq!(
                 derived,
@@ -148,7 +153,7 @@ mod tests {
             let trait_ = parse_quote!(
                 trait MyTrait<'a, 'b: 'a, T: 'static + Send> {}
             );
-            let derived = super::super::derive(&trait_).unwrap();
+            let derived = super::super::derive(&trait_, &Default::default()).unwrap();
 
             assert_eq!(
                 derived,
@@ -169,7 +174,7 @@ mod tests {
                     type Return;
                 }
             );
-            let derived = super::super::derive(&trait_).unwrap();
+            let derived = super::super::derive(&trait_, &Default::default()).unwrap();
 
             assert_eq!(
                 derived,
@@ -189,7 +194,7 @@ mod tests {
                     type Return: Clone;
                 }
             );
-            let derived = super::super::derive(&trait_).unwrap();
+            let derived = super::super::derive(&trait_, &Default::default()).unwrap();
 
             assert_eq!(
                 derived,
@@ -209,7 +214,7 @@ mod tests {
                     type r#type;
                 }
             );
-            let derived = super::super::derive(&trait_).unwrap();
+            let derived = super::super::derive(&trait_, &Default::default()).unwrap();
 
             assert_eq!(
                 derived,
@@ -232,7 +237,7 @@ mod tests {
                     type Return: Send;
                 }
             );
-            let derived = super::super::derive(&trait_).unwrap();
+            let derived = super::super::derive(&trait_, &Default::default()).unwrap();
 
             assert_eq!(
                 derived,
@@ -255,7 +260,7 @@ mod tests {
                     type Return;
                 }
             );
-            let derived = super::super::derive(&trait_).unwrap();
+            let derived = super::super::derive(&trait_, &Default::default()).unwrap();
 
             assert_eq!(
                 derived,
@@ -275,7 +280,7 @@ mod tests {
                     type Return<T>;
                 }
             );
-            let derived = super::super::derive(&trait_).unwrap();
+            let derived = super::super::derive(&trait_, &Default::default()).unwrap();
 
             assert_eq!(
                 derived,
@@ -295,7 +300,7 @@ mod tests {
                     type Return<T: 'static + Send>;
                 }
             );
-            let derived = super::super::derive(&trait_).unwrap();
+            let derived = super::super::derive(&trait_, &Default::default()).unwrap();
 
             assert_eq!(
                 derived,
@@ -317,16 +322,43 @@ mod tests {
                         Self: 'a;
                 }
             );
-            let derived = super::super::derive(&trait_).unwrap();
+            let derived = super::super::derive(&trait_, &Default::default()).unwrap();
 
             assert_eq!(
                 derived,
                 parse_quote!(
                     #[automatically_derived]
                     impl<MT: MyTrait + ?Sized> MyTrait for std::boxed::Box<MT> {
-                        type Return<'a> = <MT as MyTrait>::Return<'a>
+                        type Return<'a>
+                            = <MT as MyTrait>::Return<'a>
                         where
-                            Self: 'a;
+                            Self: 'a,
+                            MT: 'a;
+                    }
+                )
+            );
+        }
+
+        #[test]
+        fn generic_type() {
+            let trait_ = parse_quote!(
+                trait MyTrait {
+                    fn my_method(&self);
+                }
+            );
+            let options = crate::derive::Options {
+                generic_type: Some(parse_quote!(Inner)),
+                ..Default::default()
+            };
+            assert_eq!(
+                super::super::derive(&trait_, &options).unwrap(),
+                parse_quote!(
+                    #[automatically_derived]
+                    impl<Inner: MyTrait + ?Sized> MyTrait for std::boxed::Box<Inner> {
+                        #[inline]
+                        fn my_method(&self) {
+                            (*(*self)).my_method()
+                        }
                     }
                 )
             );
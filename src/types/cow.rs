@@ -1,5 +1,6 @@
 use syn::parse_quote;
 
+use crate::derive::Options;
 use crate::derive::Receiver;
 use crate::derive::WrapperType;
 
@@ -14,8 +15,12 @@ impl WrapperType for CowType {
     }
 }
 
-pub fn derive(trait_: &syn::ItemTrait) -> syn::Result<syn::ItemImpl> {
-    CowType::derive(trait_)
+pub fn derive(trait_: &syn::ItemTrait, options: &Options) -> syn::Result<syn::ItemImpl> {
+    CowType::derive(trait_, options)
+}
+
+pub fn supports(trait_: &syn::ItemTrait) -> bool {
+    CowType::supports(trait_)
 }
 
 #[cfg(test)]
@@ -29,7 +34,7 @@ mod tests {
             let trait_ = parse_quote!(
                 trait MyTrait {}
             );
-            let derived = super::super::derive(&trait_).unwrap();
+            let derived = super::super::derive(&trait_, &Default::default()).unwrap();
             assert_eq!(
                 derived,
                 parse_quote!(
@@ -47,7 +52,7 @@ mod tests {
                 }
             );
             assert_eq!(
-                super::super::derive(&trait_).unwrap(),
+                super::super::derive(&trait_, &Default::default()).unwrap(),
                 parse_quote!(
                     #[automatically_derived]
                     impl<T: Trait + ?Sized + ToOwned> Trait for std::borrow::Cow<'_, T> {
@@ -67,7 +72,7 @@ mod tests {
                     fn my_method(&mut self);
                 }
             );
-            assert!(super::super::derive(&trait_).is_err());
+            assert!(super::super::derive(&trait_, &Default::default()).is_err());
         }
 
         #[test]
@@ -77,7 +82,7 @@ mod tests {
                     fn my_method(self);
                 }
             );
-            assert!(super::super::derive(&trait_).is_err());
+            assert!(super::super::derive(&trait_, &Default::default()).is_err());
         }
 
         #[test]
@@ -87,7 +92,7 @@ mod tests {
                     fn my_method(self: Box<Self>);
                 }
             );
-            assert!(super::super::derive(&trait_).is_err());
+            assert!(super::super::derive(&trait_, &Default::default()).is_err());
         }
 
         #[test]
@@ -95,7 +100,7 @@ mod tests {
             let trait_ = parse_quote!(
                 trait MyTrait<T> {}
             );
-            let derived = super::super::derive(&trait_).unwrap();
+            let derived = super::super::derive(&trait_, &Default::default()).unwrap();
 
             assert_eq!(
                 derived,
@@ -0,0 +1,409 @@
+use syn::parse_quote;
+use syn::spanned::Spanned;
+use syn::visit::Visit;
+
+use crate::derive::await_expr;
+use crate::derive::Options;
+use crate::fanout::check_args;
+use crate::fanout::check_self;
+use crate::utils::fresh_ident;
+use crate::utils::fresh_type_ident;
+use crate::utils::generics_declaration_to_generics;
+use crate::utils::rewrite_precise_captures;
+use crate::utils::signature_to_associated_function_call;
+use crate::utils::signature_to_function_call;
+use crate::utils::signature_to_method_call;
+
+/// The name of the derive, used in error messages.
+const NAME: &str = "Fallback";
+
+/// Get the pattern of a failed result, if the given method is fallible.
+///
+/// Methods returning a `Result` fail with `Err`, and methods returning an
+/// `Option` fail with `None`.
+fn failure(sig: &syn::Signature) -> Option<syn::Pat> {
+    let ty = match sig.output {
+        syn::ReturnType::Type(_, ref ty) => &**ty,
+        syn::ReturnType::Default => return None,
+    };
+    let segment = match ty {
+        syn::Type::Path(p) if p.qself.is_none() => p.path.segments.last()?,
+        _ => return None,
+    };
+    if segment.ident == "Result" {
+        Some(parse_quote!(::core::result::Result::Err(_)))
+    } else if segment.ident == "Option" {
+        Some(parse_quote!(::core::option::Option::None))
+    } else {
+        None
+    }
+}
+
+/// Find the first `impl Trait` type in a type.
+struct ImplTraitFinder(Option<proc_macro2::Span>);
+
+impl<'ast> Visit<'ast> for ImplTraitFinder {
+    fn visit_type_impl_trait(&mut self, ty: &'ast syn::TypeImplTrait) {
+        self.0.get_or_insert(ty.span());
+    }
+}
+
+/// Check that a fallible method can be given to both values.
+///
+/// The arguments are given to both values, and the result of either value
+/// is returned, so that an `impl Trait` in the return type would have a
+/// different type for each value.
+fn check_fallible(sig: &syn::Signature) -> syn::Result<()> {
+    check_args(sig, NAME)?;
+    let mut finder = ImplTraitFinder(None);
+    if let syn::ReturnType::Type(_, ref ty) = sig.output {
+        finder.visit_type(ty);
+    }
+    match finder.0 {
+        Some(span) => {
+            let msg = format!(
+                "cannot derive `{}` for a fallible method returning `impl Trait`, since the primary and secondary values would return different types",
+                NAME
+            );
+            Err(syn::Error::new(span, msg))
+        }
+        None => Ok(()),
+    }
+}
+
+/// Generate the derived implementation of a method for a pair.
+fn derive_method(
+    m: &syn::TraitItemFn,
+    trait_ident: &syn::Ident,
+    trait_generic_names: &syn::Generics,
+    elements: &[syn::Ident; 2],
+    options: &Options,
+) -> syn::Result<syn::ImplItemFn> {
+    let override_ = options
+        .overrides
+        .iter()
+        .find(|(ident, _)| *ident == m.sig.ident);
+    let block: syn::Block = if let Some((_, path)) = override_ {
+        let mut call = signature_to_function_call(&m.sig)?;
+        call.func = Box::new(parse_quote!(#path));
+        let call = await_expr(call.into(), &m.sig);
+        parse_quote!({ #call })
+    } else {
+        check_self(&m.sig, NAME, true)?;
+        // fallible methods give their arguments to both values
+        if failure(&m.sig).is_some() {
+            check_fallible(&m.sig)?;
+        }
+
+        // build the call of the method for the primary and secondary values
+        let mut calls = Vec::with_capacity(2);
+        for (i, element) in elements.iter().enumerate() {
+            let call: syn::Expr = match m.sig.receiver() {
+                Some(r) if r.colon_token.is_some() => {
+                    let msg = format!(
+                        "cannot derive `{}` for a trait declaring methods with arbitrary receiver types",
+                        NAME
+                    );
+                    return Err(syn::Error::new(r.span(), msg));
+                }
+                Some(_) => {
+                    let index = syn::Index::from(i);
+                    let mut call = signature_to_method_call(&m.sig)?;
+                    call.receiver = Box::new(parse_quote!(self.#index));
+                    call.into()
+                }
+                None => signature_to_associated_function_call(
+                    &m.sig,
+                    trait_ident,
+                    element,
+                    trait_generic_names,
+                )?
+                .into(),
+            };
+            calls.push(await_expr(call, &m.sig));
+        }
+
+        // fallible methods call the secondary value when the primary value
+        // fails, other methods only call the primary value
+        let primary = &calls[0];
+        let secondary = &calls[1];
+        match failure(&m.sig) {
+            Some(pat) => {
+                let result = fresh_ident(&m.sig, "result");
+                parse_quote!({
+                    match #primary {
+                        #pat => #secondary,
+                        #result => #result,
+                    }
+                })
+            }
+            None => parse_quote!({ #primary }),
+        }
+    };
+
+    // `Self` cannot appear in the `use<..>` bounds of the impl, and is
+    // replaced with both values, since an impl must capture all its types
+    let mut signature = m.sig.clone();
+    let mut attrs = options.forwarded_attrs(&m.attrs);
+    attrs.extend(options.inline.attribute());
+    if rewrite_precise_captures(&mut signature, &[&elements[0], &elements[1]]) {
+        Ok(parse_quote!(#(#attrs)* #[allow(refining_impl_trait)] #signature #block))
+    } else {
+        Ok(parse_quote!(#(#attrs)* #signature #block))
+    }
+}
+
+pub fn derive(trait_: &syn::ItemTrait, options: &Options) -> syn::Result<syn::ItemImpl> {
+    let trait_ident = &trait_.ident;
+    let generic_type = options.generic_type(trait_);
+    let elements = [0usize, 1].map(|i| fresh_type_ident(trait_, &format!("{}{}", generic_type, i)));
+    let [primary, secondary] = &elements;
+
+    let mut trait_generic_names = trait_.generics.clone();
+    trait_generic_names.params = generics_declaration_to_generics(&trait_.generics.params)?;
+    let trait_args = trait_generic_names.params.iter().collect::<Vec<_>>();
+
+    // the associated types are taken from the primary value, and must be
+    // the same for the secondary value
+    let mut methods: Vec<syn::ImplItemFn> = Vec::new();
+    let mut assoc_types: Vec<syn::ImplItemType> = Vec::new();
+    let mut bindings: Vec<syn::GenericArgument> = Vec::new();
+    let mut errors: Option<syn::Error> = None;
+    for item in trait_.items.iter() {
+        let result = match item {
+            syn::TraitItem::Fn(m) => {
+                derive_method(m, trait_ident, &trait_generic_names, &elements, options)
+                    .map(|method| methods.push(method))
+            }
+            syn::TraitItem::Type(t) if !t.generics.params.is_empty() => {
+                let msg = format!(
+                    "cannot derive `{}` for a trait declaring generic associated types",
+                    NAME
+                );
+                Err(syn::Error::new(t.span(), msg))
+            }
+            syn::TraitItem::Type(t) => {
+                let t_ident = &t.ident;
+                let ty: syn::Type =
+                    parse_quote!(<#primary as #trait_ident #trait_generic_names>::#t_ident);
+                assoc_types.push(parse_quote!(type #t_ident = #ty;));
+                bindings.push(parse_quote!(#t_ident = #ty));
+                Ok(())
+            }
+            _ => Ok(()),
+        };
+        if let Err(e) = result {
+            match errors {
+                Some(ref mut err) => err.combine(e),
+                None => errors = Some(e),
+            }
+        }
+    }
+    if let Some(err) = errors {
+        return Err(err);
+    }
+
+    let mut impl_generics = trait_.generics.clone();
+    impl_generics
+        .params
+        .push(parse_quote!(#primary: #trait_ident #trait_generic_names));
+    if bindings.is_empty() {
+        impl_generics
+            .params
+            .push(parse_quote!(#secondary: #trait_ident #trait_generic_names));
+    } else {
+        impl_generics
+            .params
+            .push(parse_quote!(#secondary: #trait_ident<#(#trait_args,)* #(#bindings),*>));
+    }
+
+    let mut where_clause = trait_.generics.where_clause.clone();
+    if !options.predicates.is_empty() {
+        where_clause
+            .get_or_insert_with(|| parse_quote!(where))
+            .predicates
+            .extend(options.predicates.iter().cloned());
+    }
+    let cfg = options.cfg.iter();
+    Ok(parse_quote!(
+        #(#[cfg(#cfg)])*
+        #[automatically_derived]
+        impl #impl_generics #trait_ident #trait_generic_names for (#primary, #secondary) #where_clause {
+            #(#assoc_types)*
+            #(#methods)*
+        }
+    ))
+}
+
+pub fn supports(trait_: &syn::ItemTrait, options: &Options) -> bool {
+    trait_.items.iter().all(|item| match item {
+        syn::TraitItem::Fn(m) => {
+            let overridden = options
+                .overrides
+                .iter()
+                .any(|(ident, _)| *ident == m.sig.ident);
+            let forwarded = m.sig.receiver().is_none_or(|r| r.colon_token.is_none())
+                && check_self(&m.sig, NAME, true).is_ok()
+                && (failure(&m.sig).is_none() || check_fallible(&m.sig).is_ok());
+            overridden || forwarded
+        }
+        syn::TraitItem::Type(t) => t.generics.params.is_empty(),
+        _ => true,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    mod derive {
+
+        use syn::parse_quote;
+
+        #[test]
+        fn fallible() {
+            let trait_ = parse_quote!(
+                trait Store {
+                    fn get(&self, key: &str) -> Option<String>;
+                    fn put(&mut self, key: &str, value: &str) -> Result<(), Error>;
+                    fn name(&self) -> String;
+                }
+            );
+            assert_eq!(
+                super::super::derive(&trait_, &Default::default()).unwrap(),
+                parse_quote!(
+                    #[automatically_derived]
+                    impl<S0: Store, S1: Store> Store for (S0, S1) {
+                        #[inline]
+                        fn get(&self, key: &str) -> Option<String> {
+                            match self.0.get(key) {
+                                ::core::option::Option::None => self.1.get(key),
+                                result => result,
+                            }
+                        }
+                        #[inline]
+                        fn put(&mut self, key: &str, value: &str) -> Result<(), Error> {
+                            match self.0.put(key, value) {
+                                ::core::result::Result::Err(_) => self.1.put(key, value),
+                                result => result,
+                            }
+                        }
+                        #[inline]
+                        fn name(&self) -> String {
+                            self.0.name()
+                        }
+                    }
+                )
+            );
+        }
+
+        #[test]
+        #[rustfmt::skip]
+        fn associated() {
+            let trait_ = parse_quote!(
+                trait Client<T> {
+                    type Error;
+                    async fn connect(url: &str) -> Result<T, Self::Error>;
+                }
+            );
+            assert_eq!(
+                super::super::derive(&trait_, &Default::default()).unwrap(),
+                parse_quote!(
+                    #[automatically_derived]
+                    impl<T, C0: Client<T>, C1: Client<T, Error = <C0 as Client<T>>::Error>> Client<T> for (C0, C1) {
+                        type Error = <C0 as Client<T>>::Error;
+                        #[inline]
+                        async fn connect(url: &str) -> Result<T, Self::Error> {
+                            match <C0 as Client<T>>::connect(url).await {
+                                ::core::result::Result::Err(_) => <C1 as Client<T>>::connect(url).await,
+                                result => result,
+                            }
+                        }
+                    }
+                )
+            );
+        }
+
+        #[test]
+        fn unsupported() {
+            let trait_ = parse_quote!(
+                trait Store {
+                    fn open(path: &str) -> Result<Self, Error>;
+                }
+            );
+            assert!(super::super::derive(&trait_, &Default::default()).is_err());
+
+            let trait_ = parse_quote!(
+                trait Store {
+                    fn get(self: Box<Self>) -> Option<String>;
+                }
+            );
+            assert!(super::super::derive(&trait_, &Default::default()).is_err());
+
+            let trait_ = parse_quote!(
+                trait Sender {
+                    fn send(&self, body: Vec<u8>) -> Result<(), String>;
+                }
+            );
+            assert!(super::super::derive(&trait_, &Default::default()).is_err());
+            assert!(!super::super::supports(&trait_, &Default::default()));
+
+            let trait_ = parse_quote!(
+                trait Store {
+                    fn get(&self, key: &str) -> Option<impl std::fmt::Display>;
+                }
+            );
+            assert!(super::super::derive(&trait_, &Default::default()).is_err());
+            assert!(!super::super::supports(&trait_, &Default::default()));
+        }
+
+        #[test]
+        fn owned_args() {
+            let trait_ = parse_quote!(
+                trait Sender<S0> {
+                    fn send(&self, body: Vec<u8>) -> usize;
+                    fn retry(&self, body: &[u8], count: usize) -> Result<(), S0>;
+                }
+            );
+            assert_eq!(
+                super::super::derive(&trait_, &Default::default()).unwrap(),
+                parse_quote!(
+                    #[automatically_derived]
+                    impl<S0, S0_: Sender<S0>, S1: Sender<S0>> Sender<S0> for (S0_, S1) {
+                        #[inline]
+                        fn send(&self, body: Vec<u8>) -> usize {
+                            self.0.send(body)
+                        }
+                        #[inline]
+                        fn retry(&self, body: &[u8], count: usize) -> Result<(), S0> {
+                            match self.0.retry(body, count) {
+                                ::core::result::Result::Err(_) => self.1.retry(body, count),
+                                result => result,
+                            }
+                        }
+                    }
+                )
+            );
+        }
+
+        #[test]
+        fn precise_captures() {
+            let trait_ = parse_quote!(
+                trait Store {
+                    fn keys(&self) -> impl Iterator<Item = &str> + use<'_, Self>;
+                }
+            );
+            assert_eq!(
+                super::super::derive(&trait_, &Default::default()).unwrap(),
+                parse_quote!(
+                    #[automatically_derived]
+                    impl<S0: Store, S1: Store> Store for (S0, S1) {
+                        #[inline]
+                        #[allow(refining_impl_trait)]
+                        fn keys(&self) -> impl Iterator<Item = &str> + use<'_, S0, S1> {
+                            self.0.keys()
+                        }
+                    }
+                )
+            );
+        }
+    }
+}
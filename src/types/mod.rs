@@ -1,13 +1,21 @@
 mod arc;
+mod array;
 mod r#box;
 mod cow;
+mod fallback;
 mod r#mut;
+mod option;
 mod rc;
 mod r#ref;
+mod slice;
+mod tuple;
+mod vec;
+
+use crate::derive::Options;
 
 // ---------------------------------------------------------------------------
 
-#[derive(Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum Type {
     Box,
     Cow,
@@ -15,9 +23,31 @@ pub enum Type {
     Mut,
     Rc,
     Arc,
+    Vec,
+    Slice,
+    Array,
+    Tuple,
+    Option,
+    Fallback,
 }
 
 impl Type {
+    /// All the wrapper types that can be derived.
+    pub const ALL: &'static [Type] = &[
+        Type::Box,
+        Type::Cow,
+        Type::Ref,
+        Type::Mut,
+        Type::Rc,
+        Type::Arc,
+        Type::Vec,
+        Type::Slice,
+        Type::Array,
+        Type::Tuple,
+        Type::Option,
+        Type::Fallback,
+    ];
+
     pub fn from_str(s: &str) -> Option<Self> {
         match s {
             "Box" => Some(Type::Box),
@@ -26,24 +56,143 @@ impl Type {
             "Mut" => Some(Type::Mut),
             "Rc" => Some(Type::Rc),
             "Arc" => Some(Type::Arc),
+            "Vec" => Some(Type::Vec),
+            "Slice" => Some(Type::Slice),
+            "Array" => Some(Type::Array),
+            "Tuple" => Some(Type::Tuple),
+            "Option" => Some(Type::Option),
+            "Fallback" => Some(Type::Fallback),
             _ => None,
         }
     }
 
-    pub fn from_path(p: &syn::Path) -> Option<Self> {
-        p.segments
-            .first()
-            .and_then(|s| Self::from_str(&s.ident.to_string()))
+    /// Suggest a wrapper type for a misspelled derive option.
+    pub fn suggest(s: &str) -> Option<&'static Self> {
+        Self::ALL
+            .iter()
+            .find(|t| t.name().eq_ignore_ascii_case(s))
+            .or_else(|| {
+                Self::ALL
+                    .iter()
+                    .map(|t| (crate::utils::edit_distance(t.name(), s), t))
+                    .filter(|(d, _)| *d <= 1)
+                    .min_by_key(|(d, _)| *d)
+                    .map(|(_, t)| t)
+            })
+    }
+
+    /// Build an error for an unknown derive, with a suggestion if possible.
+    pub fn unknown(ident: &syn::Ident) -> syn::Error {
+        let name = ident.to_string();
+        let mut msg = format!("unknown blanket derive option `{}`", name);
+        if let Some(t) = Self::suggest(&name) {
+            msg.push_str(&format!(", did you mean `{}`?", t.name()));
+        }
+        let options = Self::ALL
+            .iter()
+            .map(|t| format!("`{}`", t.name()))
+            .collect::<Vec<_>>();
+        msg.push_str(&format!("\nvalid options are {}", options.join(", ")));
+        syn::Error::new(ident.span(), msg)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Type::Box => "Box",
+            Type::Cow => "Cow",
+            Type::Ref => "Ref",
+            Type::Mut => "Mut",
+            Type::Rc => "Rc",
+            Type::Arc => "Arc",
+            Type::Vec => "Vec",
+            Type::Slice => "Slice",
+            Type::Array => "Array",
+            Type::Tuple => "Tuple",
+            Type::Option => "Option",
+            Type::Fallback => "Fallback",
+        }
+    }
+
+    /// Check whether the wrapper type calls the methods of several values.
+    pub fn is_fan_out(&self) -> bool {
+        matches!(self, Type::Vec | Type::Slice | Type::Array | Type::Tuple)
     }
 
-    pub fn defer_trait_methods(&self, trait_: &syn::ItemTrait) -> syn::Result<syn::ItemImpl> {
+    /// Check whether the trait methods are supported with the given options.
+    pub fn supports(&self, trait_: &syn::ItemTrait, options: &Options) -> bool {
         match self {
-            Type::Box => self::r#box::derive(trait_),
-            Type::Cow => self::cow::derive(trait_),
-            Type::Ref => self::r#ref::derive(trait_),
-            Type::Mut => self::r#mut::derive(trait_),
-            Type::Rc => self::rc::derive(trait_),
-            Type::Arc => self::arc::derive(trait_),
+            Type::Box => self::r#box::supports(trait_),
+            Type::Cow => self::cow::supports(trait_),
+            Type::Ref => self::r#ref::supports(trait_),
+            Type::Mut => self::r#mut::supports(trait_),
+            Type::Rc => self::rc::supports(trait_),
+            Type::Arc => self::arc::supports(trait_),
+            Type::Vec => self::vec::supports(trait_, options),
+            Type::Slice => self::slice::supports(trait_, options),
+            Type::Array => self::array::supports(trait_, options),
+            Type::Tuple => self::tuple::supports(trait_, options),
+            Type::Option => self::option::supports(trait_, options),
+            Type::Fallback => self::fallback::supports(trait_, options),
+        }
+    }
+
+    /// Build a note listing the wrapper types which can be derived.
+    ///
+    /// The types are checked with the options of the `derives`, and the
+    /// fan-out types which are not derived use the method strategies given
+    /// to the derived ones.
+    pub fn supported_note(trait_: &syn::ItemTrait, derives: &[(Type, Options)]) -> String {
+        let default = Options::default();
+        let fan_out = derives
+            .iter()
+            .find(|(t, _)| t.is_fan_out())
+            .map(|(_, options)| options);
+        let supported = Self::ALL
+            .iter()
+            .filter(|t| {
+                let options = derives
+                    .iter()
+                    .find(|(d, _)| d == *t)
+                    .map(|(_, options)| options)
+                    .or(fan_out.filter(|_| t.is_fan_out()))
+                    .unwrap_or(&default);
+                t.supports(trait_, options)
+            })
+            .map(|t| format!("`{}`", t.name()))
+            .collect::<Vec<_>>();
+        if supported.is_empty() {
+            format!(
+                "none of the built-in derives support the methods of `{}`",
+                trait_.ident
+            )
+        } else {
+            format!(
+                "the methods of `{}` support deriving {}",
+                trait_.ident,
+                supported.join(", ")
+            )
         }
     }
+
+    pub fn defer_trait_methods(
+        &self,
+        trait_: &syn::ItemTrait,
+        options: &Options,
+    ) -> syn::Result<Vec<syn::ItemImpl>> {
+        let item = match self {
+            Type::Box => self::r#box::derive(trait_, options),
+            Type::Cow => self::cow::derive(trait_, options),
+            Type::Ref => self::r#ref::derive(trait_, options),
+            Type::Mut => self::r#mut::derive(trait_, options),
+            Type::Rc => self::rc::derive(trait_, options),
+            Type::Arc => self::arc::derive(trait_, options),
+            Type::Vec => self::vec::derive(trait_, options),
+            Type::Slice => self::slice::derive(trait_, options),
+            Type::Array => self::array::derive(trait_, options),
+            Type::Option => self::option::derive(trait_, options),
+            Type::Fallback => self::fallback::derive(trait_, options),
+            Type::Tuple => return self::tuple::derive(trait_, options),
+        };
+        item.map(|item| vec![item])
+    }
 }
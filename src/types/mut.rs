@@ -1,5 +1,6 @@
 use syn::parse_quote;
 
+use crate::derive::Options;
 use crate::derive::Receiver;
 use crate::derive::WrapperType;
 
@@ -13,8 +14,12 @@ impl WrapperType for MutType {
     }
 }
 
-pub fn derive(trait_: &syn::ItemTrait) -> syn::Result<syn::ItemImpl> {
-    MutType::derive(trait_)
+pub fn derive(trait_: &syn::ItemTrait, options: &Options) -> syn::Result<syn::ItemImpl> {
+    MutType::derive(trait_, options)
+}
+
+pub fn supports(trait_: &syn::ItemTrait) -> bool {
+    MutType::supports(trait_)
 }
 
 #[cfg(test)]
@@ -28,7 +33,7 @@ mod tests {
             let trait_ = parse_quote!(
                 trait MyTrait {}
             );
-            let derived = super::super::derive(&trait_).unwrap();
+            let derived = super::super::derive(&trait_, &Default::default()).unwrap();
             assert_eq!(
                 derived,
                 parse_quote!(
@@ -46,7 +51,7 @@ mod tests {
                 }
             );
             assert_eq!(
-                super::super::derive(&trait_).unwrap(),
+                super::super::derive(&trait_, &Default::default()).unwrap(),
                 parse_quote!(
                     #[automatically_derived]
                     impl<MT: MyTrait + ?Sized> MyTrait for &mut MT {
@@ -66,7 +71,7 @@ mod tests {
                     fn my_method(self);
                 }
             );
-            assert!(super::super::derive(&trait_).is_err());
+            assert!(super::super::derive(&trait_, &Default::default()).is_err());
         }
 
         #[test]
@@ -76,7 +81,7 @@ mod tests {
                     fn my_method(self: Box<Self>);
                 }
             );
-            assert!(super::super::derive(&trait_).is_err());
+            assert!(super::super::derive(&trait_, &Default::default()).is_err());
         }
 
         #[test]
@@ -84,7 +89,7 @@ mod tests {
             let trait_ = parse_quote!(
                 trait Trait<T> {}
             );
-            let derived = super::super::derive(&trait_).unwrap();
+            let derived = super::super::derive(&trait_, &Default::default()).unwrap();
 
             assert_eq!(
                 derived,
@@ -100,7 +105,7 @@ mod tests {
             let trait_ = parse_quote!(
                 trait Trait<T: 'static + Send> {}
             );
-            let derived = super::super::derive(&trait_).unwrap();
+            let derived = super::super::derive(&trait_, &Default::default()).unwrap();
 
             assert_eq!(
                 derived,
@@ -116,7 +121,7 @@ mod tests {
             let trait_ = parse_quote!(
                 trait Trait<'a, 'b: 'a, T: 'static + Send> {}
             );
-            let derived = super::super::derive(&trait_).unwrap();
+            let derived = super::super::derive(&trait_, &Default::default()).unwrap();
 
             assert_eq!(
                 derived,
@@ -137,7 +142,7 @@ mod tests {
                     type Return;
                 }
             );
-            let derived = super::super::derive(&trait_).unwrap();
+            let derived = super::super::derive(&trait_, &Default::default()).unwrap();
 
             assert_eq!(
                 derived,
@@ -157,7 +162,7 @@ mod tests {
                     type Return: Clone;
                 }
             );
-            let derived = super::super::derive(&trait_).unwrap();
+            let derived = super::super::derive(&trait_, &Default::default()).unwrap();
 
             assert_eq!(
                 derived,
@@ -177,7 +182,7 @@ mod tests {
                     type r#type;
                 }
             );
-            let derived = super::super::derive(&trait_).unwrap();
+            let derived = super::super::derive(&trait_, &Default::default()).unwrap();
 
             assert_eq!(
                 derived,
@@ -200,7 +205,7 @@ mod tests {
                     type Return: Send;
                 }
             );
-            let derived = super::super::derive(&trait_).unwrap();
+            let derived = super::super::derive(&trait_, &Default::default()).unwrap();
 
             assert_eq!(
                 derived,
@@ -223,7 +228,7 @@ mod tests {
                     type Return;
                 }
             );
-            let derived = super::super::derive(&trait_).unwrap();
+            let derived = super::super::derive(&trait_, &Default::default()).unwrap();
 
             assert_eq!(
                 derived,
@@ -243,7 +248,7 @@ mod tests {
                     type Return<T>;
                 }
             );
-            let derived = super::super::derive(&trait_).unwrap();
+            let derived = super::super::derive(&trait_, &Default::default()).unwrap();
 
             assert_eq!(
                 derived,
@@ -263,7 +268,7 @@ mod tests {
                     type Return<T: 'static + Send>;
                 }
             );
-            let derived = super::super::derive(&trait_).unwrap();
+            let derived = super::super::derive(&trait_, &Default::default()).unwrap();
 
             assert_eq!(
                 derived,
@@ -285,16 +290,18 @@ mod tests {
                         Self: 'a;
                 }
             );
-            let derived = super::super::derive(&trait_).unwrap();
+            let derived = super::super::derive(&trait_, &Default::default()).unwrap();
 
             assert_eq!(
                 derived,
                 parse_quote!(
                     #[automatically_derived]
                     impl<MT: MyTrait + ?Sized> MyTrait for &mut MT {
-                        type Return<'a> = <MT as MyTrait>::Return<'a>
+                        type Return<'a>
+                            = <MT as MyTrait>::Return<'a>
                         where
-                            Self: 'a;
+                            Self: 'a,
+                            MT: 'a;
                     }
                 )
             );
@@ -0,0 +1,331 @@
+use syn::parse_quote;
+use syn::parse_quote_spanned;
+use syn::spanned::Spanned;
+
+use crate::derive::await_expr;
+use crate::derive::Options;
+use crate::derive::Receiver;
+use crate::derive::WrapperType;
+use crate::fanout::check_self;
+use crate::utils::fresh_ident;
+use crate::utils::is_self_type;
+use crate::utils::signature_to_associated_function_call;
+use crate::utils::signature_to_method_call;
+
+struct OptionType;
+
+/// Get the value returned by a method called on `None`.
+///
+/// Methods returning `()` do nothing, and methods returning `Self` or an
+/// `Option` return `None`. Other methods return the default value of their
+/// return type, unless given another value with `#[blanket(on_none = ...)]`.
+/// Methods returning `impl Trait` are not supported, since the value
+/// returned on `None` would not have the opaque type of the wrapped value,
+/// and neither are methods returning `!`, a `Result` or a reference other
+/// than `&str` or a slice without an `on_none` value, since these types
+/// have no default value.
+fn on_none(m: &syn::TraitItemFn, options: &Options) -> syn::Result<syn::Expr> {
+    if let syn::ReturnType::Type(_, ref ty) = m.sig.output {
+        if let syn::Type::ImplTrait(_) = **ty {
+            let msg = format!(
+                "cannot derive `{}` for a method returning `impl Trait`, since the value returned on `None` would have a different type",
+                OptionType::NAME,
+            );
+            return Err(syn::Error::new(ty.span(), msg));
+        }
+    }
+    if let Some(expr) = options.on_none(&m.sig.ident) {
+        return Ok(expr.clone());
+    }
+    let ty = match m.sig.output {
+        syn::ReturnType::Default => return Ok(parse_quote!(())),
+        syn::ReturnType::Type(_, ref ty) => &**ty,
+    };
+    let no_default = |returned: &str| {
+        let msg = format!(
+            "cannot derive `{}` for a method returning {}, use `#[blanket(on_none = ...)]` to give the value returned on `None`",
+            OptionType::NAME,
+            returned,
+        );
+        Err(syn::Error::new(ty.span(), msg))
+    };
+    match ty {
+        syn::Type::Tuple(t) if t.elems.is_empty() => Ok(parse_quote!(())),
+        syn::Type::Path(p)
+            if p.qself.is_none()
+                && (p.path.is_ident("Self")
+                    || p.path.segments.last().is_some_and(|s| s.ident == "Option")) =>
+        {
+            Ok(parse_quote!(::core::option::Option::None))
+        }
+        syn::Type::Path(p)
+            if p.qself.is_none() && p.path.segments.last().is_some_and(|s| s.ident == "Result") =>
+        {
+            no_default("a `Result`")
+        }
+        syn::Type::Reference(r)
+            if !matches!(*r.elem, syn::Type::Slice(_))
+                && !matches!(*r.elem, syn::Type::Path(ref p) if p.path.is_ident("str")) =>
+        {
+            no_default("a reference")
+        }
+        syn::Type::Never(_) => no_default("`!`"),
+        _ => Ok(parse_quote_spanned!(ty.span()=> ::core::default::Default::default())),
+    }
+}
+
+impl WrapperType for OptionType {
+    const NAME: &'static str = "Option";
+    const RECEIVERS: &'static [Receiver] = &[Receiver::Ref, Receiver::Mut, Receiver::Owned];
+    const SIZED: bool = true;
+    fn wrap(ty: &syn::Ident) -> syn::Type {
+        parse_quote!(std::option::Option<#ty>)
+    }
+    fn forward_method(
+        m: &syn::TraitItemFn,
+        trait_ident: &syn::Ident,
+        generic_type: &syn::Ident,
+        trait_generic_names: &syn::Generics,
+        options: &Options,
+    ) -> syn::Result<syn::Block> {
+        // a method returning `Self` returns the result of the wrapped value
+        // wrapped in `Some`, but `Self` cannot be used anywhere else
+        let returns_self =
+            matches!(m.sig.output, syn::ReturnType::Type(_, ref ty) if is_self_type(ty));
+        check_self(&m.sig, Self::NAME, !returns_self)?;
+        let wrap = |call: syn::Expr| -> syn::Expr {
+            if returns_self {
+                parse_quote!(::core::option::Option::Some(#call))
+            } else {
+                call
+            }
+        };
+
+        // associated functions are forwarded to the wrapped type
+        let r = match m.sig.receiver() {
+            Some(r) => r,
+            None => {
+                let call = signature_to_associated_function_call(
+                    &m.sig,
+                    trait_ident,
+                    generic_type,
+                    trait_generic_names,
+                )?;
+                let call = wrap(await_expr(call.into(), &m.sig));
+                return Ok(parse_quote!({ #call }));
+            }
+        };
+        Self::check_receiver(r)?;
+
+        let inner = fresh_ident(&m.sig, "inner");
+        let mut call = signature_to_method_call(&m.sig)?;
+        call.receiver = Box::new(parse_quote!(#inner));
+        let call = wrap(await_expr(call.into(), &m.sig));
+        let none = on_none(m, options)?;
+        Ok(parse_quote!({
+            match self {
+                ::core::option::Option::Some(#inner) => #call,
+                ::core::option::Option::None => #none,
+            }
+        }))
+    }
+}
+
+pub fn derive(trait_: &syn::ItemTrait, options: &Options) -> syn::Result<syn::ItemImpl> {
+    OptionType::derive(trait_, options)
+}
+
+pub fn supports(trait_: &syn::ItemTrait, options: &Options) -> bool {
+    trait_.items.iter().all(|item| match item {
+        syn::TraitItem::Fn(m) => {
+            let returns_self =
+                matches!(m.sig.output, syn::ReturnType::Type(_, ref ty) if is_self_type(ty));
+            let forwarded = match m.sig.receiver() {
+                Some(r) => OptionType::check_receiver(r).is_ok() && on_none(m, options).is_ok(),
+                None => true,
+            };
+            let overridden = options
+                .overrides
+                .iter()
+                .any(|(ident, _)| *ident == m.sig.ident);
+            overridden || check_self(&m.sig, OptionType::NAME, !returns_self).is_ok() && forwarded
+        }
+        _ => true,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    mod derive {
+
+        use syn::parse_quote;
+
+        use crate::derive::Options;
+
+        #[test]
+        fn receivers() {
+            let trait_ = parse_quote!(
+                trait Plugin {
+                    fn name(&self) -> String;
+                    fn run(&mut self, input: &str);
+                    fn into_config(self) -> Option<Config>;
+                }
+            );
+            assert_eq!(
+                super::super::derive(&trait_, &Default::default()).unwrap(),
+                parse_quote!(
+                    #[automatically_derived]
+                    impl<P: Plugin> Plugin for std::option::Option<P> {
+                        #[inline]
+                        fn name(&self) -> String {
+                            match self {
+                                ::core::option::Option::Some(inner) => inner.name(),
+                                ::core::option::Option::None => ::core::default::Default::default(),
+                            }
+                        }
+                        #[inline]
+                        fn run(&mut self, input: &str) {
+                            match self {
+                                ::core::option::Option::Some(inner) => inner.run(input),
+                                ::core::option::Option::None => (),
+                            }
+                        }
+                        #[inline]
+                        fn into_config(self) -> Option<Config> {
+                            match self {
+                                ::core::option::Option::Some(inner) => inner.into_config(),
+                                ::core::option::Option::None => ::core::option::Option::None,
+                            }
+                        }
+                    }
+                )
+            );
+        }
+
+        #[test]
+        #[rustfmt::skip]
+        fn returns_self() {
+            let trait_ = parse_quote!(
+                trait Plugin {
+                    fn new() -> Self;
+                    fn with_level(self, level: u8) -> Self;
+                }
+            );
+            assert_eq!(
+                super::super::derive(&trait_, &Default::default()).unwrap(),
+                parse_quote!(
+                    #[automatically_derived]
+                    impl<P: Plugin> Plugin for std::option::Option<P> {
+                        #[inline]
+                        fn new() -> Self {
+                            ::core::option::Option::Some(<P as Plugin>::new())
+                        }
+                        #[inline]
+                        fn with_level(self, level: u8) -> Self {
+                            match self {
+                                ::core::option::Option::Some(inner) => ::core::option::Option::Some(inner.with_level(level)),
+                                ::core::option::Option::None => ::core::option::Option::None,
+                            }
+                        }
+                    }
+                )
+            );
+        }
+
+        #[test]
+        fn on_none() {
+            let trait_ = parse_quote!(
+                trait Plugin {
+                    fn limit(&self, input: &[u8]) -> usize;
+                    fn never(&self) -> !;
+                }
+            );
+            assert!(super::super::derive(&trait_, &Default::default()).is_err());
+
+            let options = Options {
+                on_none: vec![
+                    (parse_quote!(limit), parse_quote!(input.len())),
+                    (parse_quote!(never), parse_quote!(panic!())),
+                ],
+                ..Default::default()
+            };
+            assert_eq!(
+                super::super::derive(&trait_, &options).unwrap(),
+                parse_quote!(
+                    #[automatically_derived]
+                    impl<P: Plugin> Plugin for std::option::Option<P> {
+                        #[inline]
+                        fn limit(&self, input: &[u8]) -> usize {
+                            match self {
+                                ::core::option::Option::Some(inner) => inner.limit(input),
+                                ::core::option::Option::None => input.len(),
+                            }
+                        }
+                        #[inline]
+                        fn never(&self) -> ! {
+                            match self {
+                                ::core::option::Option::Some(inner) => inner.never(),
+                                ::core::option::Option::None => panic!(),
+                            }
+                        }
+                    }
+                )
+            );
+        }
+
+        #[test]
+        fn no_default() {
+            let trait_ = parse_quote!(
+                trait Plugin {
+                    fn run(&self) -> Result<(), Error>;
+                }
+            );
+            assert!(super::super::derive(&trait_, &Default::default()).is_err());
+            assert!(!super::super::supports(&trait_, &Default::default()));
+
+            let trait_ = parse_quote!(
+                trait Plugin {
+                    fn name(&self) -> &String;
+                }
+            );
+            assert!(super::super::derive(&trait_, &Default::default()).is_err());
+            assert!(!super::super::supports(&trait_, &Default::default()));
+
+            let trait_ = parse_quote!(
+                trait Plugin {
+                    fn name(&self) -> &str;
+                    fn data(&mut self) -> &mut [u8];
+                }
+            );
+            assert!(super::super::derive(&trait_, &Default::default()).is_ok());
+            assert!(super::super::supports(&trait_, &Default::default()));
+        }
+
+        #[test]
+        fn impl_trait() {
+            let trait_ = parse_quote!(
+                trait Plugin {
+                    fn iter(&self) -> impl Iterator<Item = u8>;
+                }
+            );
+            assert!(super::super::derive(&trait_, &Default::default()).is_err());
+
+            let options = Options {
+                on_none: vec![(parse_quote!(iter), parse_quote!(std::iter::empty()))],
+                ..Default::default()
+            };
+            assert!(super::super::derive(&trait_, &options).is_err());
+            assert!(!super::super::supports(&trait_, &options));
+        }
+
+        #[test]
+        fn unsupported() {
+            let trait_ = parse_quote!(
+                trait Plugin {
+                    fn merge(&mut self, other: Self);
+                }
+            );
+            assert!(super::super::derive(&trait_, &Default::default()).is_err());
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use syn::parse_quote;
 
+use crate::derive::Options;
 use crate::derive::Receiver;
 use crate::derive::WrapperType;
 
@@ -13,8 +14,12 @@ impl WrapperType for RefType {
     }
 }
 
-pub fn derive(trait_: &syn::ItemTrait) -> syn::Result<syn::ItemImpl> {
-    RefType::derive(trait_)
+pub fn derive(trait_: &syn::ItemTrait, options: &Options) -> syn::Result<syn::ItemImpl> {
+    RefType::derive(trait_, options)
+}
+
+pub fn supports(trait_: &syn::ItemTrait) -> bool {
+    RefType::supports(trait_)
 }
 
 #[cfg(test)]
@@ -29,7 +34,7 @@ mod tests {
                 trait Trait {}
             );
             assert_eq!(
-                super::super::derive(&trait_).unwrap(),
+                super::super::derive(&trait_, &Default::default()).unwrap(),
                 parse_quote!(
                     #[automatically_derived]
                     impl<T: Trait + ?Sized> Trait for &T {}
@@ -45,7 +50,7 @@ mod tests {
                 }
             );
             assert_eq!(
-                super::super::derive(&trait_).unwrap(),
+                super::super::derive(&trait_, &Default::default()).unwrap(),
                 parse_quote!(
                     #[automatically_derived]
                     impl<T: Trait + ?Sized> Trait for &T {
@@ -65,7 +70,7 @@ mod tests {
                     fn my_method(&mut self);
                 }
             );
-            assert!(super::super::derive(&trait_).is_err());
+            assert!(super::super::derive(&trait_, &Default::default()).is_err());
         }
 
         #[test]
@@ -75,7 +80,78 @@ mod tests {
                     fn my_method(self);
                 }
             );
-            assert!(super::super::derive(&trait_).is_err());
+            assert!(super::super::derive(&trait_, &Default::default()).is_err());
+        }
+
+        #[test]
+        fn receiver_multiple() {
+            let trait_ = parse_quote!(
+                trait Trait {
+                    fn my_method(self);
+                    fn my_other_method(&mut self);
+                    fn my_last_method(&self);
+                }
+            );
+            let err = super::super::derive(&trait_, &Default::default()).unwrap_err();
+            assert_eq!(err.into_iter().count(), 2);
+            assert!(!super::super::supports(&trait_));
+        }
+
+        #[test]
+        fn attributes() {
+            let trait_ = parse_quote!(
+                trait Trait {
+                    /// Documentation.
+                    #[cfg(feature = "x")]
+                    #[must_use]
+                    #[deprecated]
+                    #[track_caller]
+                    fn my_method(&self);
+                }
+            );
+            assert_eq!(
+                super::super::derive(&trait_, &Default::default()).unwrap(),
+                parse_quote!(
+                    #[automatically_derived]
+                    impl<T: Trait + ?Sized> Trait for &T {
+                        #[cfg(feature = "x")]
+                        #[allow(deprecated)]
+                        #[track_caller]
+                        #[inline]
+                        fn my_method(&self) {
+                            (*(*self)).my_method()
+                        }
+                    }
+                )
+            );
+        }
+
+        #[test]
+        fn attributes_allowlist() {
+            let trait_ = parse_quote!(
+                trait Trait {
+                    #[cfg(feature = "x")]
+                    #[track_caller]
+                    fn my_method(&self);
+                }
+            );
+            let options = crate::derive::Options {
+                forward_attrs: Some(vec![parse_quote!(cfg)]),
+                ..Default::default()
+            };
+            assert_eq!(
+                super::super::derive(&trait_, &options).unwrap(),
+                parse_quote!(
+                    #[automatically_derived]
+                    impl<T: Trait + ?Sized> Trait for &T {
+                        #[cfg(feature = "x")]
+                        #[inline]
+                        fn my_method(&self) {
+                            (*(*self)).my_method()
+                        }
+                    }
+                )
+            );
         }
 
         #[test]
@@ -85,7 +161,29 @@ mod tests {
                     fn my_method(self: Box<Self>);
                 }
             );
-            assert!(super::super::derive(&trait_).is_err());
+            assert!(super::super::derive(&trait_, &Default::default()).is_err());
+        }
+
+        #[test]
+        fn return_impl_trait_precise_capture() {
+            let trait_ = parse_quote!(
+                trait Trait {
+                    fn my_method(&self) -> impl Iterator<Item = u8> + use<Self>;
+                }
+            );
+            assert_eq!(
+                super::super::derive(&trait_, &Default::default()).unwrap(),
+                parse_quote!(
+                    #[automatically_derived]
+                    impl<T: Trait + ?Sized> Trait for &T {
+                        #[inline]
+                        #[allow(refining_impl_trait)]
+                        fn my_method(&self) -> impl Iterator<Item = u8> + use<T> {
+                            (*(*self)).my_method()
+                        }
+                    }
+                )
+            );
         }
 
         #[test]
@@ -93,7 +191,7 @@ mod tests {
             let trait_ = parse_quote!(
                 trait MyTrait<T> {}
             );
-            let derived = super::super::derive(&trait_).unwrap();
+            let derived = super::super::derive(&trait_, &Default::default()).unwrap();
 
             assert_eq!(
                 derived,
@@ -109,7 +207,7 @@ mod tests {
             let trait_ = parse_quote!(
                 trait MyTrait<T: 'static + Send> {}
             );
-            let derived = super::super::derive(&trait_).unwrap();
+            let derived = super::super::derive(&trait_, &Default::default()).unwrap();
 
             assert_eq!(
                 derived,
@@ -125,7 +223,7 @@ mod tests {
             let trait_ = parse_quote!(
                 trait MyTrait<'a, 'b: 'a, T: 'static + Send> {}
             );
-            let derived = super::super::derive(&trait_).unwrap();
+            let derived = super::super::derive(&trait_, &Default::default()).unwrap();
 
             assert_eq!(
                 derived,
@@ -146,7 +244,7 @@ mod tests {
                     type Return;
                 }
             );
-            let derived = super::super::derive(&trait_).unwrap();
+            let derived = super::super::derive(&trait_, &Default::default()).unwrap();
 
             assert_eq!(
                 derived,
@@ -166,7 +264,7 @@ mod tests {
                     fn run();
                 }
             );
-            let derived = super::super::derive(&trait_).unwrap();
+            let derived = super::super::derive(&trait_, &Default::default()).unwrap();
 
             assert_eq!(
                 derived,
@@ -189,7 +287,7 @@ mod tests {
                     fn run() -> usize;
                 }
             );
-            let derived = super::super::derive(&trait_).unwrap();
+            let derived = super::super::derive(&trait_, &Default::default()).unwrap();
 
             assert_eq!(
                 derived,
@@ -212,7 +310,7 @@ mod tests {
                     fn run();
                 }
             );
-            let derived = super::super::derive(&trait_).unwrap();
+            let derived = super::super::derive(&trait_, &Default::default()).unwrap();
 
             assert_eq!(
                 derived,
@@ -235,7 +333,7 @@ mod tests {
                     type Return: Clone;
                 }
             );
-            let derived = super::super::derive(&trait_).unwrap();
+            let derived = super::super::derive(&trait_, &Default::default()).unwrap();
 
             assert_eq!(
                 derived,
@@ -255,7 +353,7 @@ mod tests {
                     type r#type;
                 }
             );
-            let derived = super::super::derive(&trait_).unwrap();
+            let derived = super::super::derive(&trait_, &Default::default()).unwrap();
 
             assert_eq!(
                 derived,
@@ -278,7 +376,7 @@ mod tests {
                     type Return: Send;
                 }
             );
-            let derived = super::super::derive(&trait_).unwrap();
+            let derived = super::super::derive(&trait_, &Default::default()).unwrap();
 
             assert_eq!(
                 derived,
@@ -301,7 +399,7 @@ mod tests {
                     type Return;
                 }
             );
-            let derived = super::super::derive(&trait_).unwrap();
+            let derived = super::super::derive(&trait_, &Default::default()).unwrap();
 
             assert_eq!(
                 derived,
@@ -321,7 +419,7 @@ mod tests {
                     type Return<T>;
                 }
             );
-            let derived = super::super::derive(&trait_).unwrap();
+            let derived = super::super::derive(&trait_, &Default::default()).unwrap();
 
             assert_eq!(
                 derived,
@@ -341,7 +439,7 @@ mod tests {
                     type Return<T: 'static + Send>;
                 }
             );
-            let derived = super::super::derive(&trait_).unwrap();
+            let derived = super::super::derive(&trait_, &Default::default()).unwrap();
 
             assert_eq!(
                 derived,
@@ -363,16 +461,18 @@ mod tests {
                         Self: 'a;
                 }
             );
-            let derived = super::super::derive(&trait_).unwrap();
+            let derived = super::super::derive(&trait_, &Default::default()).unwrap();
 
             assert_eq!(
                 derived,
                 parse_quote!(
                     #[automatically_derived]
                     impl<MT: MyTrait + ?Sized> MyTrait for &MT {
-                        type Return<'a> = <MT as MyTrait>::Return<'a>
+                        type Return<'a>
+                            = <MT as MyTrait>::Return<'a>
                         where
-                            Self: 'a;
+                            Self: 'a,
+                            MT: 'a;
                     }
                 )
             );
@@ -0,0 +1,82 @@
+use syn::parse_quote;
+
+use crate::derive::Options;
+use crate::derive::Receiver;
+use crate::derive::WrapperType;
+use crate::fanout;
+
+struct SliceType;
+
+impl WrapperType for SliceType {
+    const NAME: &'static str = "Slice";
+    const RECEIVERS: &'static [Receiver] = &[Receiver::Ref, Receiver::Mut];
+    const SIZED: bool = true;
+    fn wrap(ty: &syn::Ident) -> syn::Type {
+        parse_quote!([#ty])
+    }
+    fn forward_method(
+        m: &syn::TraitItemFn,
+        _trait_ident: &syn::Ident,
+        _generic_type: &syn::Ident,
+        _trait_generic_names: &syn::Generics,
+        options: &Options,
+    ) -> syn::Result<syn::Block> {
+        fanout::forward_to_elements::<Self>(m, options)
+    }
+}
+
+pub fn derive(trait_: &syn::ItemTrait, options: &Options) -> syn::Result<syn::ItemImpl> {
+    SliceType::derive(trait_, options)
+}
+
+pub fn supports(trait_: &syn::ItemTrait, options: &Options) -> bool {
+    fanout::supports::<SliceType>(trait_, options)
+}
+
+#[cfg(test)]
+mod tests {
+    mod derive {
+
+        use syn::parse_quote;
+
+        #[test]
+        fn receivers() {
+            let trait_ = parse_quote!(
+                trait MyTrait {
+                    fn my_method(&self);
+                    fn my_method_mut(&mut self);
+                }
+            );
+            assert_eq!(
+                super::super::derive(&trait_, &Default::default()).unwrap(),
+                parse_quote!(
+                    #[automatically_derived]
+                    impl<MT: MyTrait> MyTrait for [MT] {
+                        #[inline]
+                        fn my_method(&self) {
+                            for item in self.iter() {
+                                item.my_method();
+                            }
+                        }
+                        #[inline]
+                        fn my_method_mut(&mut self) {
+                            for item in self.iter_mut() {
+                                item.my_method_mut();
+                            }
+                        }
+                    }
+                )
+            );
+        }
+
+        #[test]
+        fn receiver_self() {
+            let trait_ = parse_quote!(
+                trait MyTrait {
+                    fn my_method(self);
+                }
+            );
+            assert!(super::super::derive(&trait_, &Default::default()).is_err());
+        }
+    }
+}
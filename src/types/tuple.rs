@@ -0,0 +1,324 @@
+use syn::parse_quote;
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+
+use crate::derive::await_expr;
+use crate::derive::Options;
+use crate::fanout;
+use crate::utils::fresh_type_ident;
+use crate::utils::generics_declaration_to_generics;
+use crate::utils::is_self_type;
+use crate::utils::signature_to_associated_function_call;
+use crate::utils::signature_to_function_call;
+use crate::utils::signature_to_method_call;
+
+/// The name of the derive, used in error messages.
+const NAME: &str = "Tuple";
+
+/// The maximum arity of the tuples, if not given with `Tuple(max = ...)`.
+const MAX_ARITY: usize = 12;
+
+/// Generate the derived implementation of a method for a tuple.
+fn derive_method(
+    m: &syn::TraitItemFn,
+    trait_ident: &syn::Ident,
+    trait_generic_names: &syn::Generics,
+    elements: &[syn::Ident],
+    options: &Options,
+) -> syn::Result<syn::ImplItemFn> {
+    let override_ = options
+        .overrides
+        .iter()
+        .find(|(ident, _)| *ident == m.sig.ident);
+    let block: syn::Block = if let Some((_, path)) = override_ {
+        let mut call = signature_to_function_call(&m.sig)?;
+        call.func = Box::new(parse_quote!(#path));
+        let call = await_expr(call.into(), &m.sig);
+        parse_quote!({ #call })
+    } else {
+        // build the call of the method for every element of the tuple
+        let mut calls = Vec::with_capacity(elements.len());
+        for (i, element) in elements.iter().enumerate() {
+            let call: syn::Expr = match m.sig.receiver() {
+                Some(r) if r.colon_token.is_some() => {
+                    let msg = format!(
+                        "cannot derive `{}` for a trait declaring methods with arbitrary receiver types",
+                        NAME
+                    );
+                    return Err(syn::Error::new(r.span(), msg));
+                }
+                Some(_) => {
+                    let index = syn::Index::from(i);
+                    let mut call = signature_to_method_call(&m.sig)?;
+                    call.receiver = Box::new(parse_quote!(self.#index));
+                    call.into()
+                }
+                None => signature_to_associated_function_call(
+                    &m.sig,
+                    trait_ident,
+                    element,
+                    trait_generic_names,
+                )?
+                .into(),
+            };
+            calls.push(await_expr(call, &m.sig));
+        }
+        // methods returning `Self` build a new tuple from the results,
+        // other methods combine the results of every call
+        match m.sig.output {
+            syn::ReturnType::Type(_, ref ty)
+                if is_self_type(ty) && options.combine(&m.sig.ident).is_none() =>
+            {
+                fanout::collect(m, NAME, calls)?
+            }
+            _ => fanout::fan_out(m, NAME, options, |accumulate| {
+                calls.into_iter().map(accumulate).collect()
+            })?,
+        }
+    };
+
+    let signature = &m.sig;
+    let mut attrs = options.forwarded_attrs(&m.attrs);
+    attrs.extend(options.inline.attribute());
+    Ok(parse_quote!(#(#attrs)* #signature #block))
+}
+
+/// Generate the derived implementation for a tuple of the given arity.
+fn derive_arity(
+    trait_: &syn::ItemTrait,
+    options: &Options,
+    arity: usize,
+) -> syn::Result<syn::ItemImpl> {
+    let trait_ident = &trait_.ident;
+    let generic_type = options.generic_type(trait_);
+    let elements = (0..arity)
+        .map(|i| fresh_type_ident(trait_, &format!("{}{}", generic_type, i)))
+        .collect::<Vec<_>>();
+
+    let mut trait_generic_names = trait_.generics.clone();
+    trait_generic_names.params = generics_declaration_to_generics(&trait_.generics.params)?;
+
+    // every element of the tuple must implement the trait
+    let mut impl_generics = trait_.generics.clone();
+    for element in elements.iter() {
+        impl_generics
+            .params
+            .push(parse_quote!(#element: #trait_ident #trait_generic_names));
+    }
+
+    let mut methods: Vec<syn::ImplItemFn> = Vec::new();
+    let mut errors: Option<syn::Error> = None;
+    for item in trait_.items.iter() {
+        let result = match item {
+            syn::TraitItem::Fn(m) => {
+                derive_method(m, trait_ident, &trait_generic_names, &elements, options)
+                    .map(|method| methods.push(method))
+            }
+            syn::TraitItem::Type(t) => {
+                let msg = format!(
+                    "cannot derive `{}` for a trait declaring associated types",
+                    NAME
+                );
+                Err(syn::Error::new(t.ident.span(), msg))
+            }
+            _ => Ok(()),
+        };
+        if let Err(e) = result {
+            match errors {
+                Some(ref mut err) => err.combine(e),
+                None => errors = Some(e),
+            }
+        }
+    }
+    if let Some(err) = errors {
+        return Err(err);
+    }
+
+    let mut where_clause = trait_.generics.where_clause.clone();
+    if !options.predicates.is_empty() {
+        where_clause
+            .get_or_insert_with(|| parse_quote!(where))
+            .predicates
+            .extend(options.predicates.iter().cloned());
+    }
+    let mut tuple = elements.iter().collect::<Punctuated<_, syn::Token![,]>>();
+    if tuple.len() == 1 {
+        tuple.push_punct(Default::default());
+    }
+    let cfg = options.cfg.iter();
+    Ok(parse_quote!(
+        #(#[cfg(#cfg)])*
+        #[automatically_derived]
+        impl #impl_generics #trait_ident #trait_generic_names for (#tuple) #where_clause {
+            #(#methods)*
+        }
+    ))
+}
+
+pub fn derive(trait_: &syn::ItemTrait, options: &Options) -> syn::Result<Vec<syn::ItemImpl>> {
+    (1..=options.max.unwrap_or(MAX_ARITY))
+        .map(|arity| derive_arity(trait_, options, arity))
+        .collect()
+}
+
+pub fn supports(trait_: &syn::ItemTrait, options: &Options) -> bool {
+    trait_.items.iter().all(|item| match item {
+        syn::TraitItem::Fn(m) => {
+            let combine = options.combine(&m.sig.ident);
+            let overridden = options
+                .overrides
+                .iter()
+                .any(|(ident, _)| *ident == m.sig.ident);
+            let checked = match m.sig.output {
+                syn::ReturnType::Type(_, ref ty) if is_self_type(ty) && combine.is_none() => {
+                    fanout::check_self(&m.sig, NAME, false).is_ok()
+                        && fanout::check_args(&m.sig, NAME).is_ok()
+                }
+                _ => {
+                    fanout::check_self(&m.sig, NAME, true).is_ok()
+                        && fanout::check_args(&m.sig, NAME).is_ok()
+                        && fanout::check_output(&m.sig, NAME, combine).is_ok()
+                }
+            };
+            overridden || m.sig.receiver().is_none_or(|r| r.colon_token.is_none()) && checked
+        }
+        syn::TraitItem::Type(_) => false,
+        _ => true,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    mod derive {
+
+        use syn::parse_quote;
+
+        use crate::derive::Options;
+
+        #[test]
+        fn arity() {
+            let trait_ = parse_quote!(
+                trait Listener<T> {
+                    fn notify(&self, value: &T);
+                    fn new() -> Self;
+                }
+            );
+            let options = Options {
+                max: Some(2),
+                ..Default::default()
+            };
+            let derived = super::super::derive(&trait_, &options).unwrap();
+            assert_eq!(derived.len(), 2);
+            assert_eq!(
+                derived[0],
+                parse_quote!(
+                    #[automatically_derived]
+                    impl<T, L0: Listener<T>> Listener<T> for (L0,) {
+                        #[inline]
+                        fn notify(&self, value: &T) {
+                            self.0.notify(value);
+                        }
+                        #[inline]
+                        fn new() -> Self {
+                            (<L0 as Listener<T>>::new(),)
+                        }
+                    }
+                )
+            );
+            assert_eq!(
+                derived[1],
+                parse_quote!(
+                    #[automatically_derived]
+                    impl<T, L0: Listener<T>, L1: Listener<T>> Listener<T> for (L0, L1) {
+                        #[inline]
+                        fn notify(&self, value: &T) {
+                            self.0.notify(value);
+                            self.1.notify(value);
+                        }
+                        #[inline]
+                        fn new() -> Self {
+                            (<L0 as Listener<T>>::new(), <L1 as Listener<T>>::new())
+                        }
+                    }
+                )
+            );
+        }
+
+        #[test]
+        fn default_arity() {
+            let trait_ = parse_quote!(
+                trait Listener {
+                    fn notify(&mut self);
+                }
+            );
+            let derived = super::super::derive(&trait_, &Default::default()).unwrap();
+            assert_eq!(derived.len(), 12);
+        }
+
+        #[test]
+        fn unsupported() {
+            let trait_ = parse_quote!(
+                trait Listener {
+                    type Event;
+                }
+            );
+            assert!(super::super::derive(&trait_, &Default::default()).is_err());
+
+            let trait_ = parse_quote!(
+                trait Listener {
+                    fn merge(&self, other: Self) -> Self;
+                }
+            );
+            assert!(super::super::derive(&trait_, &Default::default()).is_err());
+
+            let trait_ = parse_quote!(
+                trait Listener {
+                    fn notify(self: Box<Self>);
+                }
+            );
+            assert!(super::super::derive(&trait_, &Default::default()).is_err());
+
+            let trait_ = parse_quote!(
+                trait Listener {
+                    fn new(name: String) -> Self;
+                }
+            );
+            assert!(super::super::derive(&trait_, &Default::default()).is_err());
+            assert!(!super::super::supports(&trait_, &Default::default()));
+
+            let trait_ = parse_quote!(
+                trait Listener {
+                    fn notify(&self, event: Vec<u8>);
+                }
+            );
+            assert!(super::super::derive(&trait_, &Default::default()).is_err());
+            assert!(!super::super::supports(&trait_, &Default::default()));
+        }
+
+        #[test]
+        fn fresh_elements() {
+            let trait_ = parse_quote!(
+                trait Listener<L0> {
+                    fn notify(&self, value: &L0);
+                }
+            );
+            let options = Options {
+                max: Some(1),
+                ..Default::default()
+            };
+            let derived = super::super::derive(&trait_, &options).unwrap();
+            assert_eq!(
+                derived[0],
+                parse_quote!(
+                    #[automatically_derived]
+                    impl<L0, L0_: Listener<L0>> Listener<L0> for (L0_,) {
+                        #[inline]
+                        fn notify(&self, value: &L0) {
+                            self.0.notify(value);
+                        }
+                    }
+                )
+            );
+        }
+    }
+}
@@ -0,0 +1,222 @@
+use syn::parse_quote;
+
+use crate::derive::Options;
+use crate::derive::Receiver;
+use crate::derive::WrapperType;
+use crate::fanout;
+
+struct VecType;
+
+impl WrapperType for VecType {
+    const NAME: &'static str = "Vec";
+    const RECEIVERS: &'static [Receiver] = &[Receiver::Ref, Receiver::Mut, Receiver::Owned];
+    const SIZED: bool = true;
+    fn wrap(ty: &syn::Ident) -> syn::Type {
+        parse_quote!(std::vec::Vec<#ty>)
+    }
+    fn forward_method(
+        m: &syn::TraitItemFn,
+        _trait_ident: &syn::Ident,
+        _generic_type: &syn::Ident,
+        _trait_generic_names: &syn::Generics,
+        options: &Options,
+    ) -> syn::Result<syn::Block> {
+        fanout::forward_to_elements::<Self>(m, options)
+    }
+}
+
+pub fn derive(trait_: &syn::ItemTrait, options: &Options) -> syn::Result<syn::ItemImpl> {
+    VecType::derive(trait_, options)
+}
+
+pub fn supports(trait_: &syn::ItemTrait, options: &Options) -> bool {
+    fanout::supports::<VecType>(trait_, options)
+}
+
+#[cfg(test)]
+mod tests {
+    mod derive {
+
+        use syn::parse_quote;
+
+        use crate::derive::Options;
+        use crate::fanout::Combine;
+
+        #[test]
+        fn empty() {
+            let trait_ = parse_quote!(
+                trait MyTrait {}
+            );
+            let derived = super::super::derive(&trait_, &Default::default()).unwrap();
+            assert_eq!(
+                derived,
+                parse_quote!(
+                    #[automatically_derived]
+                    impl<MT: MyTrait> MyTrait for std::vec::Vec<MT> {}
+                )
+            );
+        }
+
+        #[test]
+        fn receivers() {
+            let trait_ = parse_quote!(
+                trait MyTrait {
+                    fn my_method(&self, x: u32);
+                    fn my_method_mut(&mut self);
+                    async fn my_method_owned(self);
+                }
+            );
+            assert_eq!(
+                super::super::derive(&trait_, &Default::default()).unwrap(),
+                parse_quote!(
+                    #[automatically_derived]
+                    impl<MT: MyTrait> MyTrait for std::vec::Vec<MT> {
+                        #[inline]
+                        fn my_method(&self, x: u32) {
+                            for item in self.iter() {
+                                item.my_method(x);
+                            }
+                        }
+                        #[inline]
+                        fn my_method_mut(&mut self) {
+                            for item in self.iter_mut() {
+                                item.my_method_mut();
+                            }
+                        }
+                        #[inline]
+                        async fn my_method_owned(self) {
+                            for item in self {
+                                item.my_method_owned().await;
+                            }
+                        }
+                    }
+                )
+            );
+        }
+
+        #[test]
+        fn combine() {
+            let trait_ = parse_quote!(
+                trait MyTrait {
+                    fn all(&self, item: u8) -> bool;
+                    fn sum(&self) -> usize;
+                    fn check(&self) -> Result<(), String>;
+                }
+            );
+            let options = Options {
+                combine: vec![
+                    (parse_quote!(all), Combine::All),
+                    (parse_quote!(sum), Combine::Default),
+                    (parse_quote!(check), Combine::Try),
+                ],
+                ..Default::default()
+            };
+            assert_eq!(
+                super::super::derive(&trait_, &options).unwrap(),
+                parse_quote!(
+                    #[automatically_derived]
+                    impl<MT: MyTrait> MyTrait for std::vec::Vec<MT> {
+                        #[inline]
+                        fn all(&self, item: u8) -> bool {
+                            let mut result = true;
+                            for item_ in self.iter() {
+                                result &= item_.all(item);
+                            }
+                            result
+                        }
+                        #[inline]
+                        fn sum(&self) -> usize {
+                            let mut result: usize = ::core::default::Default::default();
+                            for item in self.iter() {
+                                result += item.sum();
+                            }
+                            result
+                        }
+                        #[inline]
+                        fn check(&self) -> Result<(), String> {
+                            for item in self.iter() {
+                                item.check()?;
+                            }
+                            ::core::result::Result::Ok(())
+                        }
+                    }
+                )
+            );
+        }
+
+        #[test]
+        fn unsupported() {
+            let trait_ = parse_quote!(
+                trait MyTrait {
+                    fn new() -> Self;
+                }
+            );
+            assert!(super::super::derive(&trait_, &Default::default()).is_err());
+
+            let trait_ = parse_quote!(
+                trait MyTrait {
+                    fn merge(&mut self, other: &Self);
+                }
+            );
+            assert!(super::super::derive(&trait_, &Default::default()).is_err());
+
+            let trait_ = parse_quote!(
+                trait MyTrait {
+                    fn len(&self) -> usize;
+                }
+            );
+            assert!(super::super::derive(&trait_, &Default::default()).is_err());
+
+            let trait_ = parse_quote!(
+                trait MyTrait {
+                    fn check(&self) -> Result<u8, String>;
+                }
+            );
+            let options = Options {
+                combine: vec![(parse_quote!(check), Combine::Try)],
+                ..Default::default()
+            };
+            assert!(super::super::derive(&trait_, &options).is_err());
+            assert!(!super::super::supports(&trait_, &options));
+        }
+
+        #[test]
+        fn args() {
+            let trait_ = parse_quote!(
+                trait MyTrait {
+                    fn send(&self, body: Vec<u8>);
+                }
+            );
+            assert!(super::super::derive(&trait_, &Default::default()).is_err());
+            assert!(!super::super::supports(&trait_, &Default::default()));
+
+            let trait_ = parse_quote!(
+                trait MyTrait {
+                    fn send(&self, body: &[u8], len: usize, flags: (bool, [u8; 2]));
+                    fn send_copy<T: Copy>(&self, body: T);
+                    fn send_impl(&self, body: impl Copy + std::fmt::Debug);
+                    fn send_where<T>(&mut self, body: T, out: &mut Vec<T>)
+                    where
+                        T: std::marker::Copy;
+                }
+            );
+            assert!(super::super::derive(&trait_, &Default::default()).is_ok());
+            assert!(super::super::supports(&trait_, &Default::default()));
+        }
+
+        #[test]
+        fn supports() {
+            let trait_ = parse_quote!(
+                trait MyTrait {
+                    fn len(&self) -> usize;
+                }
+            );
+            assert!(!super::super::supports(&trait_, &Default::default()));
+            let options = Options {
+                combine: vec![(parse_quote!(len), Combine::Default)],
+                ..Default::default()
+            };
+            assert!(super::super::supports(&trait_, &options));
+        }
+    }
+}
@@ -1,4 +1,9 @@
+use std::collections::HashSet;
+
 use quote::quote_spanned;
+use syn::ext::IdentExt;
+use syn::visit::Visit;
+use syn::visit_mut::VisitMut;
 use syn::{parse_quote, punctuated::Punctuated, spanned::Spanned, GenericParam, Token};
 
 /// Convert a function signature to a function call with the same arguments.
@@ -149,35 +154,67 @@ pub fn deref_expr(expr: syn::Expr) -> syn::Expr {
     })
 }
 
+/// Collect the identifiers used in a trait declaration, outside of bodies.
+#[derive(Default)]
+struct IdentCollector {
+    idents: HashSet<String>,
+}
+
+impl<'ast> Visit<'ast> for IdentCollector {
+    fn visit_ident(&mut self, ident: &'ast syn::Ident) {
+        self.idents.insert(ident.to_string());
+    }
+
+    fn visit_block(&mut self, _block: &'ast syn::Block) {}
+}
+
 /// Build a generic identifier suitable for the given trait.
 ///
 /// This function extracts the initials of the trait identifier. If this results
-/// in a generic type identifier already present in the generics of that trait,
+/// in an identifier already used in the trait declaration, such as a generic
+/// parameter of the trait or of a method, or a type mentioned in a signature,
 /// as many underscores are added to the end of the identifier.
 ///
 /// Given a trait `MyAmazingTrait`, get `MAT`.
 pub fn trait_to_generic_ident(trait_: &syn::ItemTrait) -> syn::Ident {
-    let mut raw = trait_
+    let raw = trait_
         .ident
         .to_string()
         .chars()
         .filter(|c| c.is_uppercase())
         .collect::<String>();
-    loop {
-        if !trait_.generics.params.iter().any(|g| match g {
-            syn::GenericParam::Type(param) if param.ident == raw => true,
-            syn::GenericParam::Const(param) if param.ident == raw => true,
-            _ => false,
-        }) {
-            break;
-        } else {
-            raw.push('_');
-        }
-    }
+    fresh_type_ident(trait_, &raw)
+}
 
+/// Build an identifier not used in the given trait declaration.
+///
+/// As many underscores as needed are added to the end of `base`, so that
+/// the identifier can be used as a generic type of an implementation of
+/// the trait.
+pub fn fresh_type_ident(trait_: &syn::ItemTrait, base: &str) -> syn::Ident {
+    let mut collector = IdentCollector::default();
+    collector.visit_item_trait(trait_);
+    let mut raw = base.to_string();
+    while collector.idents.contains(&raw) {
+        raw.push('_');
+    }
     syn::Ident::new(&raw, trait_.ident.span())
 }
 
+/// Build an identifier not used in the given method signature.
+///
+/// As many underscores as needed are added to the end of `base`, so that
+/// the identifier does not shadow an argument or a type of the signature.
+pub fn fresh_ident(sig: &syn::Signature, base: &str) -> syn::Ident {
+    let mut collector = IdentCollector::default();
+    collector.visit_signature(sig);
+    let mut raw = base.to_string();
+    while collector.idents.contains(&raw) {
+        raw.push('_');
+    }
+    syn::Ident::new(&raw, sig.ident.span())
+}
+
 /// Convert a generic type declaration to a generic with the same arguments.
 ///
 /// Given a generic section `<T: 'static + Send>`, get simply `<T>`.
@@ -208,6 +245,145 @@ pub fn generics_declaration_to_generics(
         .collect()
 }
 
+/// Check whether the given type is exactly `Self`.
+pub fn is_self_type(ty: &syn::Type) -> bool {
+    match ty {
+        syn::Type::Path(p) => p.qself.is_none() && p.path.is_ident("Self"),
+        _ => false,
+    }
+}
+
+/// Build the where clause of a forwarded generic associated type.
+///
+/// The predicates of the trait declaration are kept as-is, since the impl
+/// must not declare weaker bounds than the trait, but every lifetime bound
+/// on `Self` is also added to the generic type, which is what the forwarded
+/// `<T as Trait>::Type<'a>` requires to be well-formed.
+///
+/// Given `where Self: 'a` and a generic type `T`, get `where Self: 'a, T: 'a`.
+pub fn gat_where_clause(
+    where_clause: Option<&syn::WhereClause>,
+    generic_type: &syn::Ident,
+) -> Option<syn::WhereClause> {
+    let mut where_clause = where_clause?.clone();
+    let mut implied: Vec<syn::WherePredicate> = Vec::new();
+    for predicate in where_clause.predicates.iter() {
+        if let syn::WherePredicate::Type(ref p) = predicate {
+            if p.lifetimes.is_none() && is_self_type(&p.bounded_ty) {
+                let lifetimes = p.bounds.iter().filter_map(|b| match b {
+                    syn::TypeParamBound::Lifetime(l) => Some(l),
+                    _ => None,
+                });
+                let bounds: Punctuated<_, Token![+]> = lifetimes.collect();
+                if !bounds.is_empty() {
+                    implied.push(parse_quote!(#generic_type: #bounds));
+                }
+            }
+        }
+    }
+    where_clause.predicates.extend(implied);
+    Some(where_clause)
+}
+
+/// Rewrite the `use<..>` bounds of a forwarded method signature.
+///
+/// `Self` cannot be named in the precise capturing bounds of an impl block,
+/// so it is replaced with the generic types of the implementation. Returns
+/// `true` if any bound was rewritten.
+///
+/// Given `fn iter(&self) -> impl Iterator + use<Self>` and a generic type
+/// `T`, get `fn iter(&self) -> impl Iterator + use<T>`.
+pub fn rewrite_precise_captures(sig: &mut syn::Signature, generic_types: &[&syn::Ident]) -> bool {
+    struct Rewriter<'a> {
+        generic_types: &'a [&'a syn::Ident],
+        rewritten: bool,
+    }
+
+    impl VisitMut for Rewriter<'_> {
+        fn visit_precise_capture_mut(&mut self, capture: &mut syn::PreciseCapture) {
+            let mut params = Punctuated::new();
+            for param in std::mem::take(&mut capture.params) {
+                match param {
+                    syn::CapturedParam::Ident(ref ident) if ident == "Self" => {
+                        params.extend(
+                            self.generic_types
+                                .iter()
+                                .map(|ty| syn::CapturedParam::Ident((*ty).clone())),
+                        );
+                        self.rewritten = true;
+                    }
+                    _ => params.push(param),
+                }
+            }
+            capture.params = params;
+        }
+    }
+
+    let mut rewriter = Rewriter {
+        generic_types,
+        rewritten: false,
+    };
+    rewriter.visit_return_type_mut(&mut sig.output);
+    rewriter.rewritten
+}
+
+/// Compute the Levenshtein distance between two strings.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.chars().enumerate() {
+        let mut previous = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous + usize::from(ca != *cb);
+            previous = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(previous + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// Replace the `Self` type with a generic type.
+pub struct SelfReplacer<'a> {
+    pub generic_type: &'a syn::Ident,
+}
+
+impl VisitMut for SelfReplacer<'_> {
+    fn visit_path_mut(&mut self, path: &mut syn::Path) {
+        if let Some(segment) = path.segments.first_mut() {
+            if segment.ident == "Self" {
+                segment.ident = self.generic_type.clone();
+            }
+        }
+        syn::visit_mut::visit_path_mut(self, path);
+    }
+}
+
+/// Convert a `CamelCase` identifier to `snake_case`.
+pub fn to_snake_case(ident: &syn::Ident) -> syn::Ident {
+    let name = ident.unraw().to_string();
+    let chars = name.chars().collect::<Vec<_>>();
+    let mut snake = String::with_capacity(name.len());
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            // start a new word after a lowercase letter or a digit, or at the
+            // last capital letter of an acronym such as `HTTPVisit`
+            let prev = chars[i - 1];
+            let next_lower = chars.get(i + 1).is_some_and(|n| n.is_lowercase());
+            if prev.is_lowercase() || prev.is_ascii_digit() || (prev.is_uppercase() && next_lower) {
+                snake.push('_');
+            }
+        }
+        snake.extend(c.to_lowercase());
+    }
+    // keywords such as `match` are only valid as raw identifiers
+    if syn::parse_str::<syn::Ident>(&snake).is_ok() {
+        syn::Ident::new(&snake, ident.span())
+    } else {
+        syn::Ident::new_raw(&snake, ident.span())
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -231,6 +407,44 @@ mod tests {
         assert_eq!(dereffed, parse_quote!((*self)));
     }
 
+    #[test]
+    fn gat_where_clause() {
+        let generic_type: syn::Ident = parse_quote!(T);
+
+        let where_clause: syn::WhereClause = parse_quote!(where Self: 'a);
+        let expected: syn::WhereClause = parse_quote!(where Self: 'a, T: 'a);
+        let rewritten = super::gat_where_clause(Some(&where_clause), &generic_type);
+        assert_eq!(rewritten, Some(expected));
+
+        let where_clause: syn::WhereClause = parse_quote!(where Self: 'a + 'b + Clone, U: 'a);
+        let expected: syn::WhereClause =
+            parse_quote!(where Self: 'a + 'b + Clone, U: 'a, T: 'a + 'b);
+        let rewritten = super::gat_where_clause(Some(&where_clause), &generic_type);
+        assert_eq!(rewritten, Some(expected));
+
+        let where_clause: syn::WhereClause = parse_quote!(where Self::Assoc: 'a);
+        let rewritten = super::gat_where_clause(Some(&where_clause), &generic_type);
+        assert_eq!(rewritten, Some(where_clause));
+
+        assert_eq!(super::gat_where_clause(None, &generic_type), None);
+    }
+
+    #[test]
+    fn rewrite_precise_captures() {
+        let generic_type: syn::Ident = parse_quote!(T);
+
+        let mut sig: syn::Signature = parse_quote!(fn iter(&self) -> impl Iterator + use<'_, Self>);
+        assert!(super::rewrite_precise_captures(&mut sig, &[&generic_type]));
+        assert_eq!(
+            sig,
+            parse_quote!(fn iter(&self) -> impl Iterator + use<'_, T>)
+        );
+
+        let mut sig: syn::Signature = parse_quote!(fn iter(&self) -> impl Iterator + '_);
+        assert!(!super::rewrite_precise_captures(&mut sig, &[&generic_type]));
+        assert_eq!(sig, parse_quote!(fn iter(&self) -> impl Iterator + '_));
+    }
+
     #[test]
     fn trait_to_generic_ident() {
         let trait_ = syn::parse_quote!(
@@ -250,5 +464,33 @@ mod tests {
         );
         let expected: syn::Ident = syn::parse_quote!(T_);
         assert_eq!(super::trait_to_generic_ident(&trait_), expected);
+
+        let trait_ = syn::parse_quote!(
+            trait Trait {
+                type Item: T;
+                fn get<T_>(&self, item: T_) -> T__;
+            }
+        );
+        let expected: syn::Ident = syn::parse_quote!(T___);
+        assert_eq!(super::trait_to_generic_ident(&trait_), expected);
+    }
+
+    #[test]
+    fn edit_distance() {
+        assert_eq!(super::edit_distance("Box", "Box"), 0);
+        assert_eq!(super::edit_distance("Bx", "Box"), 1);
+        assert_eq!(super::edit_distance("Arc", "Rc"), 2);
+        assert_eq!(super::edit_distance("Cow", "Mut"), 3);
+        assert_eq!(super::edit_distance("", "Ref"), 3);
+    }
+
+    #[test]
+    fn to_snake_case() {
+        let snake = |s: &str| super::to_snake_case(&syn::parse_str(s).unwrap()).to_string();
+        assert_eq!(snake("Visit"), "visit");
+        assert_eq!(snake("FoldMut"), "fold_mut");
+        assert_eq!(snake("HTTPVisitor"), "http_visitor");
+        assert_eq!(snake("Visit2D"), "visit2_d");
+        assert_eq!(snake("r#Match"), "r#match");
     }
 }
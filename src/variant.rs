@@ -0,0 +1,399 @@
+use syn::parse::Parse;
+use syn::parse::ParseStream;
+use syn::parse_quote;
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+
+use crate::derive::Options;
+use crate::types::Type;
+use crate::utils::gat_where_clause;
+use crate::utils::generics_declaration_to_generics;
+use crate::utils::signature_to_associated_function_call;
+
+/// A variant of a trait with additional bounds on the returned futures.
+///
+/// Given `SendService: Send`, generate a trait `SendService` requiring
+/// `Send` from its implementors and from every future it returns. The
+/// futures only get the `Send` bound of the trait, unless given other
+/// bounds with `SendService: Send + Sync, futures = Send + Sync`.
+pub struct Variant {
+    pub ident: syn::Ident,
+    pub bounds: Punctuated<syn::TypeParamBound, syn::Token![+]>,
+    pub future_bounds: Punctuated<syn::TypeParamBound, syn::Token![+]>,
+}
+
+impl Parse for Variant {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let ident = input.parse()?;
+        input.parse::<syn::Token![:]>()?;
+        let bounds: Punctuated<syn::TypeParamBound, syn::Token![+]> =
+            Punctuated::parse_separated_nonempty(input)?;
+        let future_bounds = if input.parse::<Option<syn::Token![,]>>()?.is_some() {
+            let key: syn::Ident = input.parse()?;
+            if key != "futures" {
+                return Err(syn::Error::new(key.span(), "expected `futures`"));
+            }
+            input.parse::<syn::Token![=]>()?;
+            Punctuated::parse_separated_nonempty(input)?
+        } else {
+            bounds
+                .iter()
+                .filter(|bound| is_bound(bound, "Send"))
+                .cloned()
+                .collect()
+        };
+        Ok(Self {
+            ident,
+            bounds,
+            future_bounds,
+        })
+    }
+}
+
+/// Check whether a bound is the given trait.
+fn is_bound(bound: &syn::TypeParamBound, name: &str) -> bool {
+    match bound {
+        syn::TypeParamBound::Trait(t) => t.path.segments.last().is_some_and(|s| s.ident == name),
+        _ => false,
+    }
+}
+
+impl Variant {
+    /// Generate the variant trait declaration from the original trait.
+    ///
+    /// The variant bounds are added to the supertraits, and every `async fn`
+    /// is desugared into a method returning `impl Future<Output = ...>` with
+    /// the future bounds, which are also added to methods already returning
+    /// an `impl Trait`.
+    ///
+    /// Default `async` bodies borrowing `self` return futures holding a
+    /// `&Self`, so they can only be `Send` if `Self` is also `Sync`.
+    pub fn make_trait(&self, trait_: &syn::ItemTrait) -> syn::Result<syn::ItemTrait> {
+        let mut variant = trait_.clone();
+        let bounds = &self.bounds;
+        let future_bounds = &self.future_bounds;
+        let send = self.future_bounds.iter().any(|b| is_bound(b, "Send"));
+        let sync = self.has_bound("Sync");
+
+        variant.ident = self.ident.clone();
+        variant.colon_token.get_or_insert_with(Default::default);
+        variant.supertraits.extend(bounds.iter().cloned());
+
+        for item in variant.items.iter_mut() {
+            if let syn::TraitItem::Fn(ref mut m) = item {
+                if let Some(async_) = m.sig.asyncness.take() {
+                    let span = async_.span();
+                    let output = match m.sig.output {
+                        syn::ReturnType::Default => parse_quote!(()),
+                        syn::ReturnType::Type(_, ref ty) => ty.as_ref().clone(),
+                    };
+                    let mut future: syn::TypeImplTrait =
+                        parse_quote!(impl std::future::Future<Output = #output>);
+                    future.bounds.extend(future_bounds.iter().cloned());
+                    m.sig.output = parse_quote!(-> #future);
+                    let shared = m.sig.receiver().is_some_and(|r| match *r.ty {
+                        syn::Type::Reference(ref ty) => ty.mutability.is_none(),
+                        _ => false,
+                    });
+                    if send && !sync && shared && m.default.is_some() {
+                        let msg = format!(
+                            "the default body of `{}` borrows `self`, so its future is only `Send` if `{}` is `Sync`: add `Sync` to the bounds of the variant",
+                            m.sig.ident, self.ident
+                        );
+                        return Err(syn::Error::new(m.sig.ident.span(), msg));
+                    }
+                    if let Some(block) = m.default.take() {
+                        m.default = Some(syn::parse_quote_spanned!(span=> { async move #block }));
+                    }
+                } else if let syn::ReturnType::Type(_, ref mut ty) = m.sig.output {
+                    if let syn::Type::ImplTrait(ref mut impl_) = ty.as_mut() {
+                        impl_.bounds.extend(future_bounds.iter().cloned());
+                    }
+                }
+            }
+        }
+
+        Ok(variant)
+    }
+
+    /// Check whether the variant bounds include the given trait.
+    fn has_bound(&self, name: &str) -> bool {
+        self.bounds.iter().any(|bound| is_bound(bound, name))
+    }
+
+    /// Get the predicates required by a wrapper type to implement the variant.
+    ///
+    /// The wrappers sharing their value are only `Send` or `Sync` if the
+    /// wrapped type is `Sync`, which the variant bounds do not imply, and
+    /// `Rc` is never `Send` nor `Sync`.
+    pub fn wrapper_predicates(
+        &self,
+        wrapper: &Type,
+        generic_type: &syn::Ident,
+    ) -> syn::Result<Vec<syn::WherePredicate>> {
+        let send = self.has_bound("Send");
+        let sync = self.has_bound("Sync");
+        if !send && !sync {
+            return Ok(Vec::new());
+        }
+        match wrapper {
+            Type::Rc => {
+                let msg = format!(
+                    "cannot derive `Rc` for the variant `{}`, since `Rc` is neither `Send` nor `Sync`",
+                    self.ident
+                );
+                Err(syn::Error::new(self.ident.span(), msg))
+            }
+            Type::Ref => Ok(vec![parse_quote!(#generic_type: Sync)]),
+            Type::Arc => Ok(vec![parse_quote!(#generic_type: Send + Sync)]),
+            Type::Cow => {
+                let owned: syn::Type = parse_quote!(<#generic_type as ToOwned>::Owned);
+                let mut predicates = vec![parse_quote!(#generic_type: Sync)];
+                if send {
+                    predicates.push(parse_quote!(#owned: Send));
+                }
+                if sync {
+                    predicates.push(parse_quote!(#owned: Sync));
+                }
+                Ok(predicates)
+            }
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    /// Generate the blanket implementation of the original trait for every
+    /// implementor of the variant trait.
+    pub fn derive_original(
+        &self,
+        trait_: &syn::ItemTrait,
+        variant: &syn::ItemTrait,
+        options: &Options,
+    ) -> syn::Result<syn::ItemImpl> {
+        let trait_ident = &trait_.ident;
+        let variant_ident = &variant.ident;
+        let generic_type = options.generic_type(variant);
+
+        let trait_generics = &trait_.generics;
+        let where_clause = &trait_.generics.where_clause;
+        let mut impl_generics = trait_generics.clone();
+        let mut trait_generic_names = trait_generics.clone();
+        trait_generic_names.params = generics_declaration_to_generics(&trait_generics.params)?;
+
+        let mut items: Vec<syn::ImplItem> = Vec::new();
+        let mut sized = false;
+        for item in trait_.items.iter() {
+            match item {
+                syn::TraitItem::Fn(m) => {
+                    if let Some(r) = m.sig.receiver() {
+                        sized |= r.reference.is_none();
+                    }
+                    let mut call: syn::Expr = signature_to_associated_function_call(
+                        &m.sig,
+                        variant_ident,
+                        &generic_type,
+                        &trait_generic_names,
+                    )?
+                    .into();
+                    if m.sig.asyncness.is_some() {
+                        call = parse_quote!(#call.await);
+                    }
+                    let signature = &m.sig;
+                    let attrs = options.forwarded_attrs(&m.attrs);
+                    items.push(parse_quote!(#(#attrs)* #[inline] #signature { #call }));
+                }
+                syn::TraitItem::Type(t) => {
+                    let t_ident = &t.ident;
+                    let attrs = &t.attrs;
+                    let t_generics = &t.generics;
+                    let where_clause =
+                        gat_where_clause(t.generics.where_clause.as_ref(), &generic_type);
+                    let mut t_generic_names = t_generics.clone();
+                    t_generic_names.params = generics_declaration_to_generics(&t_generics.params)?;
+                    items.push(parse_quote!(
+                        #(#attrs)* type #t_ident #t_generics = <#generic_type as #variant_ident #trait_generic_names>::#t_ident #t_generic_names #where_clause ;
+                    ));
+                }
+                _ => (),
+            }
+        }
+
+        let mut bounds: Punctuated<syn::TypeParamBound, syn::Token![+]> =
+            parse_quote!(#variant_ident #trait_generic_names);
+        if !sized {
+            bounds.push(parse_quote!(?Sized));
+        }
+        impl_generics
+            .params
+            .push(parse_quote!(#generic_type: #bounds));
+
+        Ok(parse_quote!(
+            #[automatically_derived]
+            impl #impl_generics #trait_ident #trait_generic_names for #generic_type #where_clause {
+                #(#items)*
+            }
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use syn::parse_quote;
+
+    use super::Variant;
+    use crate::types::Type;
+
+    #[test]
+    fn make_trait() {
+        let variant: Variant = parse_quote!(SendService: Send);
+        let trait_ = parse_quote!(
+            trait Service {
+                async fn call(&self, request: u32) -> u32;
+                async fn ready(&mut self) {}
+                fn iter(&self) -> impl Iterator<Item = u32>;
+                fn name(&self) -> &str;
+            }
+        );
+        assert_eq!(
+            variant.make_trait(&trait_).unwrap(),
+            parse_quote!(
+                trait SendService: Send {
+                    fn call(&self, request: u32) -> impl std::future::Future<Output = u32> + Send;
+                    fn ready(&mut self) -> impl std::future::Future<Output = ()> + Send {
+                        async move {}
+                    }
+                    fn iter(&self) -> impl Iterator<Item = u32> + Send;
+                    fn name(&self) -> &str;
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn make_trait_future_bounds() {
+        let trait_ = parse_quote!(
+            trait Service {
+                async fn call(&self);
+                fn iter(&self) -> impl Iterator<Item = u32>;
+            }
+        );
+
+        let variant: Variant = parse_quote!(StaticService: Send + Sync + 'static);
+        assert_eq!(
+            variant.make_trait(&trait_).unwrap(),
+            parse_quote!(
+                trait StaticService: Send + Sync + 'static {
+                    fn call(&self) -> impl std::future::Future<Output = ()> + Send;
+                    fn iter(&self) -> impl Iterator<Item = u32> + Send;
+                }
+            )
+        );
+
+        let variant: Variant = parse_quote!(SyncService: Sync, futures = Send + Sync);
+        assert_eq!(
+            variant.make_trait(&trait_).unwrap(),
+            parse_quote!(
+                trait SyncService: Sync {
+                    fn call(&self) -> impl std::future::Future<Output = ()> + Send + Sync;
+                    fn iter(&self) -> impl Iterator<Item = u32> + Send + Sync;
+                }
+            )
+        );
+
+        let variant: Variant = parse_quote!(LocalService: 'static);
+        assert_eq!(
+            variant.make_trait(&trait_).unwrap(),
+            parse_quote!(
+                trait LocalService: 'static {
+                    fn call(&self) -> impl std::future::Future<Output = ()>;
+                    fn iter(&self) -> impl Iterator<Item = u32>;
+                }
+            )
+        );
+
+        assert!(syn::parse_str::<Variant>("SendService: Send, future = Send").is_err());
+    }
+
+    #[test]
+    fn make_trait_shared_default() {
+        let variant: Variant = parse_quote!(SendService: Send);
+        let trait_ = parse_quote!(
+            trait Service {
+                async fn ready(&self) {}
+                async fn reset(&mut self) {}
+            }
+        );
+        assert!(variant.make_trait(&trait_).is_err());
+
+        let trait_ = parse_quote!(
+            trait Service {
+                async fn ready(&self);
+                async fn reset(&mut self) {}
+            }
+        );
+        assert!(variant.make_trait(&trait_).is_ok());
+    }
+
+    #[test]
+    fn wrapper_predicates() {
+        let generic_type: syn::Ident = parse_quote!(SS);
+        let variant: Variant = parse_quote!(SendService: Send);
+        assert_eq!(
+            variant
+                .wrapper_predicates(&Type::Ref, &generic_type)
+                .unwrap(),
+            vec![parse_quote!(SS: Sync)]
+        );
+        assert_eq!(
+            variant
+                .wrapper_predicates(&Type::Arc, &generic_type)
+                .unwrap(),
+            vec![parse_quote!(SS: Send + Sync)]
+        );
+        assert!(variant
+            .wrapper_predicates(&Type::Box, &generic_type)
+            .unwrap()
+            .is_empty());
+        assert!(variant
+            .wrapper_predicates(&Type::Rc, &generic_type)
+            .is_err());
+
+        let variant: Variant = parse_quote!(StaticService: 'static);
+        assert!(variant
+            .wrapper_predicates(&Type::Rc, &generic_type)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn derive_original() {
+        let variant: Variant = parse_quote!(SendService: Send + Sync);
+        let trait_ = parse_quote!(
+            trait Service<R> {
+                type Response;
+                async fn call(&self, request: R) -> Self::Response;
+                fn name() -> String;
+            }
+        );
+        let variant_trait = variant.make_trait(&trait_).unwrap();
+        assert_eq!(
+            variant
+                .derive_original(&trait_, &variant_trait, &Default::default())
+                .unwrap(),
+            parse_quote!(
+                #[automatically_derived]
+                impl<R, SS: SendService<R> + ?Sized> Service<R> for SS {
+                    type Response = <SS as SendService<R>>::Response;
+                    #[inline]
+                    async fn call(&self, request: R) -> Self::Response {
+                        <SS as SendService<R>>::call(self, request).await
+                    }
+                    #[inline]
+                    fn name() -> String {
+                        <SS as SendService<R>>::name()
+                    }
+                }
+            )
+        );
+    }
+}
@@ -0,0 +1,472 @@
+use std::collections::HashSet;
+
+use syn::parse::{Parse, ParseStream};
+use syn::parse_quote;
+use syn::punctuated::Punctuated;
+
+use crate::default::{defer_trait_methods, DefaultModule};
+use crate::utils::{fresh_ident, to_snake_case, trait_to_generic_ident};
+
+/// The arguments of the `#[visitor(...)]` attribute.
+pub struct VisitorArgs {
+    /// The name of the generated visitor trait.
+    pub ident: syn::Ident,
+    /// The name of the generated default module, inferred from the trait
+    /// name if not given.
+    pub module: Option<syn::Ident>,
+}
+
+/// Parse the arguments of `#[visitor(Visit, module = ...)]`.
+impl Parse for VisitorArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let ident = input.parse()?;
+        let mut module: Option<syn::Ident> = None;
+        while !input.is_empty() {
+            input.parse::<syn::Token![,]>()?;
+            if input.is_empty() {
+                break;
+            }
+            let key = input.parse::<syn::Ident>()?;
+            match key.to_string().as_str() {
+                "module" => {
+                    input.parse::<syn::Token![=]>()?;
+                    if module.replace(input.parse()?).is_some() {
+                        return Err(syn::Error::new(key.span(), "duplicate module given"));
+                    }
+                }
+                _ => {
+                    let msg = "unexpected visitor option, expected `module`";
+                    return Err(syn::Error::new(key.span(), msg));
+                }
+            }
+        }
+        Ok(Self { ident, module })
+    }
+}
+
+/// A data type of the module for which a visitor method is generated.
+struct Node<'a> {
+    ident: &'a syn::Ident,
+    generics: &'a syn::Generics,
+    data: Data<'a>,
+    method: syn::Ident,
+}
+
+/// The fields of a data type, either of a struct or of each enum variant.
+enum Data<'a> {
+    Struct(&'a syn::Fields),
+    Enum(&'a Punctuated<syn::Variant, syn::Token![,]>),
+}
+
+/// Find the type of the module named by `path`, if any.
+///
+/// Only paths relative to the module, such as `Expr` or `self::Expr`, name
+/// its types, since `other::Expr` is a different type with the same name.
+fn find_node<'n, 'a>(nodes: &'n [Node<'a>], path: &syn::Path) -> Option<&'n Node<'a>> {
+    if path.leading_colon.is_some() {
+        return None;
+    }
+    let segments = path.segments.iter().collect::<Vec<_>>();
+    let segment = match segments.as_slice() {
+        [segment] => segment,
+        [module, segment] if module.ident == "self" => segment,
+        _ => return None,
+    };
+    nodes.iter().find(|n| *n.ident == segment.ident)
+}
+
+/// Turn an expression into a statement, terminated by a semicolon if needed.
+fn to_stmt(expr: syn::Expr) -> syn::Stmt {
+    match expr {
+        syn::Expr::ForLoop(_) | syn::Expr::If(_) => syn::Stmt::Expr(expr, None),
+        _ => syn::Stmt::Expr(expr, Some(Default::default())),
+    }
+}
+
+/// Generate the statements visiting a value of type `ty`.
+///
+/// `expr` is an expression of type `&ty`. Types of the module are visited
+/// with their visitor method, and `Box`, `Vec`, `Option` and references
+/// are followed to reach them. Other types are not visited, and no
+/// statement is generated for them.
+fn visit_type(
+    nodes: &[Node],
+    visitor: &syn::Ident,
+    ty: &syn::Type,
+    expr: syn::Expr,
+    depth: usize,
+) -> Option<syn::Expr> {
+    match ty {
+        syn::Type::Paren(p) => visit_type(nodes, visitor, &p.elem, expr, depth),
+        syn::Type::Group(g) => visit_type(nodes, visitor, &g.elem, expr, depth),
+        syn::Type::Reference(r) => {
+            visit_type(nodes, visitor, &r.elem, parse_quote!(&**#expr), depth)
+        }
+        syn::Type::Path(p) if p.qself.is_none() => {
+            let segment = p.path.segments.last()?;
+            let inner = match segment.arguments {
+                syn::PathArguments::AngleBracketed(ref args) => {
+                    args.args.iter().find_map(|arg| match arg {
+                        syn::GenericArgument::Type(ty) => Some(ty),
+                        _ => None,
+                    })
+                }
+                _ => None,
+            };
+            let item = quote::format_ident!("item{}", depth);
+            match (segment.ident.to_string().as_str(), inner) {
+                ("Box", Some(inner)) => {
+                    visit_type(nodes, visitor, inner, parse_quote!(&**#expr), depth)
+                }
+                ("Vec", Some(inner)) => {
+                    let body = visit_type(nodes, visitor, inner, parse_quote!(#item), depth + 1)
+                        .map(to_stmt)?;
+                    Some(parse_quote!(for #item in #expr.iter() { #body }))
+                }
+                ("Option", Some(inner)) => {
+                    let body = visit_type(nodes, visitor, inner, parse_quote!(#item), depth + 1)
+                        .map(to_stmt)?;
+                    Some(parse_quote!(if let Some(#item) = #expr { #body }))
+                }
+                (_, _) => {
+                    let node = find_node(nodes, &p.path)?;
+                    let method = &node.method;
+                    Some(parse_quote!(#visitor.#method(#expr)))
+                }
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Generate the statements visiting the given fields, bound to `bindings`.
+fn visit_fields(
+    nodes: &[Node],
+    visitor: &syn::Ident,
+    fields: &syn::Fields,
+) -> (Vec<Option<syn::Ident>>, Vec<syn::Stmt>) {
+    let mut bindings = Vec::new();
+    let mut stmts = Vec::new();
+    for (i, field) in fields.iter().enumerate() {
+        let binding = quote::format_ident!("f{}", i);
+        match visit_type(nodes, visitor, &field.ty, parse_quote!(#binding), 0) {
+            Some(stmt) => {
+                bindings.push(Some(binding));
+                stmts.push(to_stmt(stmt));
+            }
+            None => bindings.push(None),
+        }
+    }
+    (bindings, stmts)
+}
+
+/// Build a pattern binding the visited fields of a struct or enum variant.
+fn fields_pattern(
+    path: syn::Path,
+    fields: &syn::Fields,
+    bindings: &[Option<syn::Ident>],
+) -> syn::Pat {
+    match fields {
+        syn::Fields::Unit => parse_quote!(#path),
+        syn::Fields::Unnamed(_) => {
+            let pats = bindings.iter().map(|b| match b {
+                Some(binding) => quote::quote!(#binding),
+                None => quote::quote!(_),
+            });
+            parse_quote!(#path(#(#pats),*))
+        }
+        syn::Fields::Named(named) => {
+            let pats = named
+                .named
+                .iter()
+                .zip(bindings)
+                .filter_map(|(field, b)| {
+                    let ident = field.ident.as_ref()?;
+                    let binding = b.as_ref()?;
+                    Some(quote::quote!(#ident: #binding))
+                })
+                .collect::<Vec<_>>();
+            parse_quote!(#path { #(#pats,)* .. })
+        }
+    }
+}
+
+/// Generate a visitor trait and its default module for the types of `module`.
+///
+/// The trait has one `visit_<type>` method for every struct and enum of the
+/// module, deferring to a function of the same name in the default module,
+/// which visits the fields of the type.
+pub fn derive_visitor(mut module: syn::ItemMod, args: &VisitorArgs) -> syn::Result<syn::ItemMod> {
+    let items = match module.content {
+        Some((_, ref mut items)) => items,
+        None => {
+            let msg = "expected a module with inline content";
+            return Err(syn::Error::new(module.ident.span(), msg));
+        }
+    };
+
+    // collect the types of the module
+    let mut nodes = Vec::new();
+    let mut methods = HashSet::new();
+    for item in items.iter() {
+        let (ident, generics, data) = match item {
+            syn::Item::Struct(s) => (&s.ident, &s.generics, Data::Struct(&s.fields)),
+            syn::Item::Enum(e) => (&e.ident, &e.generics, Data::Enum(&e.variants)),
+            _ => continue,
+        };
+        let method = quote::format_ident!(
+            "visit_{}",
+            to_snake_case(ident).to_string().trim_start_matches("r#")
+        );
+        if !methods.insert(method.to_string()) {
+            let msg = format!("duplicate visitor method `{}`", method);
+            return Err(syn::Error::new(ident.span(), msg));
+        }
+        nodes.push(Node {
+            ident,
+            generics,
+            data,
+            method,
+        });
+    }
+
+    // generate the trait declaration
+    let trait_ident = &args.ident;
+    let methods = nodes.iter().map(|node| {
+        let ident = node.ident;
+        let method = &node.method;
+        let (impl_generics, ty_generics, where_clause) = node.generics.split_for_impl();
+        let doc = format!(" Visit a [`{}`] node.", ident);
+        quote::quote!(
+            #[doc = #doc]
+            fn #method #impl_generics (&mut self, node: &#ident #ty_generics) #where_clause;
+        )
+    });
+    let doc = format!(
+        " A visitor for the types of the [`{}`] module.",
+        module.ident
+    );
+    let trait_: syn::ItemTrait = parse_quote!(
+        #[doc = #doc]
+        pub trait #trait_ident {
+            #(#methods)*
+        }
+    );
+
+    // generate the default functions visiting the fields of each type
+    let generic_type = trait_to_generic_ident(&trait_);
+    let mut functions: Vec<syn::ItemFn> = Vec::new();
+    for node in nodes.iter() {
+        let ident = node.ident;
+        let method = &node.method;
+        let mut generics = node.generics.clone();
+        generics
+            .params
+            .insert(0, parse_quote!(#generic_type: #trait_ident + ?Sized));
+        let (impl_generics, _, where_clause) = generics.split_for_impl();
+        let (_, ty_generics, _) = node.generics.split_for_impl();
+
+        // name the visitor after the generic type, without shadowing any
+        // identifier used by the signature of the function
+        let sig: syn::Signature =
+            parse_quote!(fn #method #impl_generics (node: &#ident #ty_generics) #where_clause);
+        let visitor = fresh_ident(&sig, &generic_type.to_string().to_lowercase());
+        let body: Vec<syn::Stmt> = match node.data {
+            Data::Struct(fields) => {
+                let (bindings, stmts) = visit_fields(&nodes, &visitor, fields);
+                if stmts.is_empty() {
+                    Vec::new()
+                } else {
+                    let pat = fields_pattern(parse_quote!(#ident), fields, &bindings);
+                    parse_quote!(
+                        let #pat = node;
+                        #(#stmts)*
+                    )
+                }
+            }
+            Data::Enum(variants) => {
+                let arms = variants.iter().map(|variant| {
+                    let v_ident = &variant.ident;
+                    let (bindings, stmts) = visit_fields(&nodes, &visitor, &variant.fields);
+                    let pat = match variant.fields {
+                        syn::Fields::Unit => parse_quote!(#ident::#v_ident),
+                        syn::Fields::Unnamed(_) if stmts.is_empty() => {
+                            parse_quote!(#ident::#v_ident(..))
+                        }
+                        syn::Fields::Named(_) if stmts.is_empty() => {
+                            parse_quote!(#ident::#v_ident { .. })
+                        }
+                        _ => fields_pattern(
+                            parse_quote!(#ident::#v_ident),
+                            &variant.fields,
+                            &bindings,
+                        ),
+                    };
+                    quote::quote!(#pat => { #(#stmts)* })
+                });
+                parse_quote!(
+                    match node {
+                        #(#arms)*
+                    }
+                )
+            }
+        };
+        let doc = format!(" Visit the fields of a [`{}`] node.", ident);
+        functions.push(parse_quote!(
+            #[doc = #doc]
+            #[allow(unused_variables)]
+            pub fn #method #impl_generics (#visitor: &mut #generic_type, node: &#ident #ty_generics) #where_clause {
+                #(#body)*
+            }
+        ));
+    }
+
+    // defer the trait methods to the default functions
+    let default_ident = match args.module {
+        Some(ref module) => module.clone(),
+        None => to_snake_case(trait_ident),
+    };
+    let default = DefaultModule::new(Some(default_ident.clone().into()));
+    let (trait_, _) = defer_trait_methods(trait_, Some(&default), &[])?;
+    let doc = format!(
+        " Default implementations of the [`{}`] methods.",
+        trait_ident
+    );
+    items.push(syn::Item::Trait(trait_));
+    items.push(parse_quote!(
+        #[doc = #doc]
+        pub mod #default_ident {
+            #[allow(unused_imports)]
+            use super::*;
+
+            #(#functions)*
+        }
+    ));
+
+    Ok(module)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use syn::parse_quote;
+
+    #[test]
+    fn derive_visitor() {
+        let module = parse_quote!(
+            mod ast {
+                pub struct Program {
+                    pub name: String,
+                    pub body: Vec<Stmt>,
+                }
+                pub enum Stmt {
+                    Expr(Expr),
+                    Let {
+                        name: String,
+                        value: Option<Box<Expr>>,
+                    },
+                    Empty,
+                }
+                pub struct Expr(pub u32);
+            }
+        );
+        let args = parse_quote!(Visit);
+        let module = super::derive_visitor(module, &args).unwrap();
+        let items = &module.content.unwrap().1;
+        assert_eq!(items.len(), 5);
+        assert_eq!(
+            items[3],
+            parse_quote!(
+                #[doc = " A visitor for the types of the [`ast`] module."]
+                pub trait Visit {
+                    #[doc = " Visit a [`Program`] node."]
+                    fn visit_program(&mut self, node: &Program) {
+                        visit::visit_program(self, node)
+                    }
+                    #[doc = " Visit a [`Stmt`] node."]
+                    fn visit_stmt(&mut self, node: &Stmt) {
+                        visit::visit_stmt(self, node)
+                    }
+                    #[doc = " Visit a [`Expr`] node."]
+                    fn visit_expr(&mut self, node: &Expr) {
+                        visit::visit_expr(self, node)
+                    }
+                }
+            )
+        );
+        assert_eq!(
+            items[4],
+            parse_quote!(
+                #[doc = " Default implementations of the [`Visit`] methods."]
+                pub mod visit {
+                    #[allow(unused_imports)]
+                    use super::*;
+
+                    #[doc = " Visit the fields of a [`Program`] node."]
+                    #[allow(unused_variables)]
+                    pub fn visit_program<V: Visit + ?Sized>(v: &mut V, node: &Program) {
+                        let Program { body: f1, .. } = node;
+                        for item0 in f1.iter() {
+                            v.visit_stmt(item0);
+                        }
+                    }
+                    #[doc = " Visit the fields of a [`Stmt`] node."]
+                    #[allow(unused_variables)]
+                    pub fn visit_stmt<V: Visit + ?Sized>(v: &mut V, node: &Stmt) {
+                        match node {
+                            Stmt::Expr(f0) => {
+                                v.visit_expr(f0);
+                            }
+                            Stmt::Let { value: f1, .. } => {
+                                if let Some(item0) = f1 {
+                                    v.visit_expr(&**item0);
+                                }
+                            }
+                            Stmt::Empty => {}
+                        }
+                    }
+                    #[doc = " Visit the fields of a [`Expr`] node."]
+                    #[allow(unused_variables)]
+                    pub fn visit_expr<V: Visit + ?Sized>(v: &mut V, node: &Expr) {}
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn derive_visitor_paths() {
+        let module = parse_quote!(
+            mod ast {
+                pub struct Call<v> {
+                    pub callee: self::Expr,
+                    pub args: Vec<other::Expr>,
+                    pub extra: v,
+                }
+                pub struct Expr(pub u32);
+            }
+        );
+        let args = parse_quote!(Visit);
+        let module = super::derive_visitor(module, &args).unwrap();
+        let items = &module.content.unwrap().1;
+        assert_eq!(
+            items[3],
+            parse_quote!(
+                #[doc = " Default implementations of the [`Visit`] methods."]
+                pub mod visit {
+                    #[allow(unused_imports)]
+                    use super::*;
+
+                    #[doc = " Visit the fields of a [`Call`] node."]
+                    #[allow(unused_variables)]
+                    pub fn visit_call<V: Visit + ?Sized, v>(v_: &mut V, node: &Call<v>) {
+                        let Call { callee: f0, .. } = node;
+                        v_.visit_expr(f0);
+                    }
+                    #[doc = " Visit the fields of a [`Expr`] node."]
+                    #[allow(unused_variables)]
+                    pub fn visit_expr<V: Visit + ?Sized>(v: &mut V, node: &Expr) {}
+                }
+            )
+        );
+    }
+}
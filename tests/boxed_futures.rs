@@ -0,0 +1,116 @@
+#![allow(unused)]
+
+extern crate blanket;
+use blanket::blanket;
+
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::Context;
+use std::task::Poll;
+use std::task::Waker;
+
+#[blanket(boxed_futures, derive(Box, Ref))]
+pub trait Store: Sync {
+    async fn get(&self, key: &str) -> Option<u32>;
+    async fn contains(&self, key: &str) -> bool {
+        self.get(key).await.is_some()
+    }
+    fn name(&self) -> &str;
+}
+
+struct Single {
+    key: String,
+    value: u32,
+}
+
+impl Store for Single {
+    fn get<'life0, 'life1, 'blanket>(
+        &'life0 self,
+        key: &'life1 str,
+    ) -> Pin<Box<dyn Future<Output = Option<u32>> + Send + 'blanket>>
+    where
+        'life0: 'blanket,
+        'life1: 'blanket,
+    {
+        Box::pin(async move { (key == self.key).then_some(self.value) })
+    }
+
+    fn name(&self) -> &str {
+        "single"
+    }
+}
+
+struct Constant(u32);
+
+#[blanket(boxed_futures)]
+impl Store for Constant {
+    async fn get(&self, _key: &str) -> Option<u32> {
+        Some(self.0)
+    }
+
+    fn name(&self) -> &str {
+        "constant"
+    }
+}
+
+#[blanket(boxed_futures(?Send))]
+pub trait LocalStore {
+    async fn get(&self, key: &str) -> Option<Rc<u32>>;
+}
+
+struct Shared(Rc<u32>);
+
+#[blanket(boxed_futures(?Send))]
+impl LocalStore for Shared {
+    async fn get(&self, _key: &str) -> Option<Rc<u32>> {
+        let value = self.0.clone();
+        async {}.await;
+        Some(value)
+    }
+}
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = std::pin::pin!(future);
+    let mut context = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return output;
+        }
+    }
+}
+
+#[test]
+fn test_dyn() {
+    let store: Box<dyn Store> = Box::new(Single {
+        key: String::from("a"),
+        value: 1,
+    });
+    assert_eq!(block_on(store.get("a")), Some(1));
+    assert_eq!(block_on(store.get("b")), None);
+    assert!(block_on(store.contains("a")));
+    assert_eq!(store.name(), "single");
+}
+
+#[test]
+fn test_derived() {
+    let store = Single {
+        key: String::from("a"),
+        value: 1,
+    };
+    let stores: Vec<Box<dyn Store>> = vec![Box::new(&store), Box::new(Box::new(&store))];
+    for s in stores {
+        assert_eq!(block_on(s.get("a")), Some(1));
+        assert!(!block_on(s.contains("b")));
+    }
+}
+
+#[test]
+fn test_impl() {
+    let store: Box<dyn Store> = Box::new(Constant(2));
+    assert_eq!(block_on(store.get("a")), Some(2));
+    assert!(block_on(store.contains("b")));
+
+    let local: Box<dyn LocalStore> = Box::new(Shared(Rc::new(3)));
+    assert_eq!(block_on(local.get("a")).as_deref(), Some(&3));
+}
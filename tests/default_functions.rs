@@ -0,0 +1,140 @@
+#![allow(unused)]
+
+extern crate blanket;
+use blanket::blanket;
+
+#[blanket(default(module = visit, prefix = "default_"))]
+pub trait Visitor {
+    fn visit_str(&mut self, s: &str);
+    #[blanket(default_fn = chars::visit_char)]
+    fn visit_char(&mut self, c: char);
+}
+
+#[blanket(default(module = visit, prefix = "default_"))]
+pub trait Printer {
+    fn print(&self) -> String;
+}
+
+pub mod visit {
+    use super::Printer;
+    use super::Visitor;
+    use super::Walker;
+
+    pub fn default_visit_str<V: Visitor + ?Sized>(v: &mut V, s: &str) {
+        for c in s.chars() {
+            v.visit_char(c);
+        }
+    }
+
+    pub fn default_print<P: Printer + ?Sized>(p: &P) -> String {
+        String::from("printer")
+    }
+
+    pub fn default_walk<W: Walker + ?Sized>(w: &W) -> String {
+        w.name()
+    }
+
+    pub fn helper() {}
+}
+
+pub mod chars {
+    use super::Visitor;
+
+    pub fn visit_char<V: Visitor + ?Sized>(v: &mut V, c: char) {}
+}
+
+#[test]
+fn test_prefix() {
+    #[derive(Default)]
+    struct CharCounter {
+        count: usize,
+    }
+
+    impl Visitor for CharCounter {
+        fn visit_char(&mut self, c: char) {
+            self.count += 1
+        }
+    }
+
+    let mut counter = CharCounter::default();
+    counter.visit_str("Hello");
+    assert_eq!(counter.count, 5);
+}
+
+#[test]
+fn test_default_fn() {
+    struct Noop;
+
+    impl Visitor for Noop {}
+    impl Printer for Noop {}
+
+    Noop.visit_str("Hello");
+    assert_eq!(Noop.print(), "printer");
+}
+
+#[blanket(default(module = visit, prefix = "default_", partial))]
+pub trait Walker {
+    fn walk(&self) -> String;
+    fn name(&self) -> String {
+        String::from("walker")
+    }
+    #[blanket(no_default)]
+    fn steps(&self) -> usize;
+}
+
+#[test]
+fn test_partial() {
+    struct Stroll;
+
+    impl Walker for Stroll {
+        fn steps(&self) -> usize {
+            3
+        }
+    }
+
+    assert_eq!(Stroll.walk(), "walker");
+    assert_eq!(Stroll.steps(), 3);
+}
+
+#[blanket(default(module = fold))]
+pub trait Folder<T: Clone> {
+    type Output;
+    fn fold(&self, t: &T) -> T;
+    fn fold_with<'a, U: Into<T>>(&'a self, u: U, s: &'a str) -> &'a str;
+    fn finish(self) -> Option<Self::Output>
+    where
+        Self: Sized;
+}
+
+pub mod fold {
+    use super::Folder;
+
+    pub fn fold<T: Clone, F: Folder<T> + ?Sized>(_f: &F, t: &T) -> T {
+        t.clone()
+    }
+
+    pub fn fold_with<'a, T: Clone, F: Folder<T> + ?Sized, U: Into<T>>(
+        _f: &'a F,
+        _u: U,
+        s: &'a str,
+    ) -> &'a str {
+        s
+    }
+
+    pub fn finish<T: Clone, F: Folder<T>>(_f: F) -> Option<F::Output> {
+        None
+    }
+}
+
+#[test]
+fn test_generic() {
+    struct Identity;
+
+    impl Folder<u8> for Identity {
+        type Output = u8;
+    }
+
+    assert_eq!(Identity.fold(&1), 1);
+    assert_eq!(Identity.fold_with(1u8, "x"), "x");
+    assert_eq!(Identity.finish(), None);
+}
@@ -0,0 +1,40 @@
+#![allow(unused)]
+
+extern crate blanket;
+use blanket::blanket;
+
+#[blanket(default)]
+pub trait CharVisitor {
+    fn visit_str(&mut self, s: &str);
+    fn visit_char(&mut self, c: char);
+}
+
+pub mod char_visitor {
+    use super::CharVisitor;
+
+    pub fn visit_str<V: CharVisitor + ?Sized>(v: &mut V, s: &str) {
+        for c in s.chars() {
+            v.visit_char(c);
+        }
+    }
+
+    pub fn visit_char<V: CharVisitor + ?Sized>(v: &mut V, c: char) {}
+}
+
+#[test]
+fn test_inferred() {
+    #[derive(Default)]
+    struct CharCounter {
+        count: usize,
+    }
+
+    impl CharVisitor for CharCounter {
+        fn visit_char(&mut self, c: char) {
+            self.count += 1
+        }
+    }
+
+    let mut counter = CharCounter::default();
+    counter.visit_str("Hello");
+    assert_eq!(counter.count, 5);
+}
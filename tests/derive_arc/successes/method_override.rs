@@ -0,0 +1,34 @@
+extern crate blanket;
+extern crate impls;
+
+use std::sync::Arc;
+
+use blanket::blanket;
+use impls::impls;
+
+#[blanket(derive(Arc))]
+pub trait Identified {
+    #[blanket(override(Arc = arc_id))]
+    fn id(&self) -> usize;
+}
+
+fn arc_id<T: Identified + ?Sized>(this: &Arc<T>) -> usize {
+    Arc::as_ptr(this) as *const () as usize
+}
+
+struct Item;
+
+impl Identified for Item {
+    fn id(&self) -> usize {
+        0
+    }
+}
+
+fn main() {
+    assert!(impls!(Item:      Identified));
+    assert!(impls!(Arc<Item>: Identified));
+
+    let arc = Arc::new(Item);
+    assert_eq!(arc.id(), Arc::as_ptr(&arc) as usize);
+    assert_eq!(Item.id(), 0);
+}
@@ -0,0 +1,40 @@
+extern crate blanket;
+extern crate impls;
+
+use std::rc::Rc;
+use std::sync::Arc;
+
+use blanket::blanket;
+use impls::impls;
+
+#[blanket(
+    generic = T,
+    derive(Arc(cfg = any()), Box(where = T: Send + 'static, inline = never), Rc(inline = always))
+)]
+pub trait Counter {
+    fn count(&self) -> u8;
+}
+
+struct Zero;
+
+impl Counter for Zero {
+    fn count(&self) -> u8 {
+        0
+    }
+}
+
+struct Local(Rc<u8>);
+
+impl Counter for Local {
+    fn count(&self) -> u8 {
+        *self.0
+    }
+}
+
+fn main() {
+    assert!(impls!(Box<Zero>:   Counter));
+    assert!(impls!(Rc<Zero>:    Counter));
+    assert!(impls!(Arc<Zero>:  !Counter));
+    assert!(impls!(Box<Local>: !Counter));
+    assert!(impls!(Rc<Local>:   Counter));
+}
@@ -0,0 +1,38 @@
+extern crate blanket;
+extern crate impls;
+
+use blanket::blanket;
+use impls::impls;
+
+pub struct T(u8);
+
+#[blanket(derive(Box))]
+pub trait Tracker {
+    fn track(&self, value: T) -> u8;
+}
+
+#[blanket(derive(Box), generic = Inner)]
+pub trait Recorder {
+    fn record<R: Into<u8>>(&self, value: R) -> u8;
+}
+
+struct Noop;
+
+impl Tracker for Noop {
+    fn track(&self, value: T) -> u8 {
+        value.0
+    }
+}
+
+impl Recorder for Noop {
+    fn record<R: Into<u8>>(&self, value: R) -> u8 {
+        value.into()
+    }
+}
+
+fn main() {
+    assert!(impls!(Noop:      Tracker & Recorder));
+    assert!(impls!(Box<Noop>: Tracker & Recorder));
+    assert_eq!(Box::new(Noop).track(T(1)), 1);
+    assert_eq!(Box::new(Noop).record(2u8), 2);
+}
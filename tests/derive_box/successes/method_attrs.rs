@@ -0,0 +1,42 @@
+#![deny(warnings)]
+
+extern crate blanket;
+extern crate impls;
+
+use blanket::blanket;
+use impls::impls;
+
+#[blanket(derive(Box))]
+pub trait Counter {
+    /// Get the current count.
+    #[must_use]
+    fn count(&self) -> u8;
+    #[cfg(any())]
+    fn missing(&self) -> Missing;
+    #[deprecated(note = "use `count` instead")]
+    fn get(&self) -> u8;
+    #[track_caller]
+    #[allow(clippy::needless_lifetimes)]
+    fn check<'a>(&'a self) -> &'a str;
+}
+
+struct Zero;
+
+#[allow(deprecated)]
+impl Counter for Zero {
+    fn count(&self) -> u8 {
+        0
+    }
+    fn get(&self) -> u8 {
+        0
+    }
+    fn check(&self) -> &str {
+        "zero"
+    }
+}
+
+fn main() {
+    assert!(impls!(Zero:      Counter));
+    assert!(impls!(Box<Zero>: Counter));
+    assert_eq!(Box::new(Zero).count(), 0);
+}
@@ -1,4 +1,3 @@
-#![feature(associated_type_bounds)]
 
 extern crate blanket;
 extern crate impls;
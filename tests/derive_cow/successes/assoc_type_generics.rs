@@ -0,0 +1,34 @@
+use blanket::blanket;
+use impls::impls;
+
+#[blanket(derive(Cow))]
+pub trait View {
+    type Slice<'a>: AsRef<[u8]>
+    where
+        Self: 'a;
+    fn view(&self) -> Self::Slice<'_>;
+}
+
+#[derive(Clone)]
+struct Buffer {
+    data: Vec<u8>,
+}
+
+impl View for Buffer {
+    type Slice<'a> = &'a [u8] where Self: 'a;
+    fn view(&self) -> Self::Slice<'_> {
+        &self.data
+    }
+}
+
+fn len<V: View>(v: V) -> usize {
+    v.view().as_ref().len()
+}
+
+fn main() {
+    assert!(impls!(Buffer: View));
+    assert!(impls!(std::borrow::Cow<'static, Buffer>: View));
+
+    let buffer = Buffer { data: vec![1, 2, 3] };
+    assert_eq!(len(std::borrow::Cow::<Buffer>::Owned(buffer)), 3);
+}
@@ -0,0 +1,41 @@
+use blanket::blanket;
+use impls::impls;
+
+#[blanket(derive(Mut))]
+pub trait LendingIterator {
+    type Item<'a>
+    where
+        Self: 'a;
+    fn next(&mut self) -> Option<Self::Item<'_>>;
+}
+
+struct WindowsMut<'t> {
+    slice: &'t mut [u8],
+    start: usize,
+}
+
+impl<'t> LendingIterator for WindowsMut<'t> {
+    type Item<'a> = &'a mut [u8] where Self: 'a;
+    fn next(&mut self) -> Option<Self::Item<'_>> {
+        let window = self.slice[self.start..].get_mut(..2)?;
+        self.start += 1;
+        Some(window)
+    }
+}
+
+fn count<L: LendingIterator>(mut l: L) -> usize {
+    let mut n = 0;
+    while l.next().is_some() {
+        n += 1;
+    }
+    n
+}
+
+fn main() {
+    assert!(impls!(WindowsMut<'static>: LendingIterator));
+    assert!(impls!(&mut WindowsMut<'static>: LendingIterator));
+
+    let mut data = [1, 2, 3, 4];
+    let mut windows = WindowsMut { slice: &mut data, start: 0 };
+    assert_eq!(count(&mut windows), 3);
+}
@@ -0,0 +1,34 @@
+use blanket::blanket;
+use impls::impls;
+
+#[blanket(derive(Rc))]
+pub trait View {
+    type Slice<'a>: AsRef<[u8]>
+    where
+        Self: 'a;
+    fn view(&self) -> Self::Slice<'_>;
+}
+
+#[derive(Clone)]
+struct Buffer {
+    data: Vec<u8>,
+}
+
+impl View for Buffer {
+    type Slice<'a> = &'a [u8] where Self: 'a;
+    fn view(&self) -> Self::Slice<'_> {
+        &self.data
+    }
+}
+
+fn len<V: View>(v: V) -> usize {
+    v.view().as_ref().len()
+}
+
+fn main() {
+    assert!(impls!(Buffer: View));
+    assert!(impls!(std::rc::Rc<Buffer>: View));
+
+    let buffer = Buffer { data: vec![1, 2, 3] };
+    assert_eq!(len(std::rc::Rc::new(buffer)), 3);
+}
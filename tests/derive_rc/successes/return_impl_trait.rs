@@ -0,0 +1,41 @@
+use std::future::Future;
+
+use blanket::blanket;
+use impls::impls;
+
+#[blanket(derive(Rc))]
+pub trait Bytes {
+    fn iter(&self) -> impl Iterator<Item = &u8> + '_;
+    fn owned(&self) -> impl Iterator<Item = u8> + use<Self>;
+    fn checksum(&self) -> impl Future<Output = u8> + Send;
+}
+
+#[derive(Clone)]
+struct Buffer {
+    data: Vec<u8>,
+}
+
+impl Bytes for Buffer {
+    fn iter(&self) -> impl Iterator<Item = &u8> + '_ {
+        self.data.iter()
+    }
+    fn owned(&self) -> impl Iterator<Item = u8> + use<> {
+        self.data.clone().into_iter()
+    }
+    fn checksum(&self) -> impl Future<Output = u8> + Send {
+        let sum = self.data.iter().fold(0u8, |acc, x| acc.wrapping_add(*x));
+        async move { sum }
+    }
+}
+
+fn total<B: Bytes>(b: B) -> usize {
+    b.iter().count() + b.owned().count()
+}
+
+fn main() {
+    assert!(impls!(Buffer: Bytes));
+    assert!(impls!(std::rc::Rc<Buffer>: Bytes));
+
+    let buffer = Buffer { data: vec![1, 2, 3] };
+    assert_eq!(total(std::rc::Rc::new(buffer)), 6);
+}
@@ -0,0 +1,34 @@
+use blanket::blanket;
+use impls::impls;
+
+#[blanket(derive(Ref))]
+pub trait View {
+    type Slice<'a>: AsRef<[u8]>
+    where
+        Self: 'a;
+    fn view(&self) -> Self::Slice<'_>;
+}
+
+#[derive(Clone)]
+struct Buffer {
+    data: Vec<u8>,
+}
+
+impl View for Buffer {
+    type Slice<'a> = &'a [u8] where Self: 'a;
+    fn view(&self) -> Self::Slice<'_> {
+        &self.data
+    }
+}
+
+fn len<V: View>(v: V) -> usize {
+    v.view().as_ref().len()
+}
+
+fn main() {
+    assert!(impls!(Buffer: View));
+    assert!(impls!(&Buffer: View));
+
+    let buffer = Buffer { data: vec![1, 2, 3] };
+    assert_eq!(len(&buffer), 3);
+}
@@ -0,0 +1,131 @@
+#![allow(unused)]
+
+extern crate blanket;
+use blanket::blanket;
+
+use std::fmt::Debug;
+use std::fmt::Display;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::Context;
+use std::task::Poll;
+use std::task::Waker;
+
+#[blanket(dyn_compatible = DynSource)]
+trait Source {
+    type Item;
+    fn new() -> Self
+    where
+        Self: Sized;
+    fn name(&self) -> String;
+    fn items(&self) -> impl Iterator<Item = &Self::Item>;
+    async fn fetch(&mut self, index: usize) -> Option<Self::Item>;
+}
+
+#[blanket(dyn_compatible = DynLogger)]
+trait Logger {
+    fn log<D: Display + ?Sized>(&self, message: &D) -> String;
+    fn into_lines(self) -> Vec<String>;
+}
+
+#[blanket(dyn_compatible = DynLabel)]
+trait Label {
+    fn label(&self) -> impl Debug + Display;
+    fn describe<D: Debug + Display + ?Sized>(&self, value: &D) -> String;
+    fn width(&self) -> usize;
+}
+
+#[blanket(assert_dyn_compatible, boxed_futures)]
+trait Counter<T>: Sync {
+    type Output;
+    fn new() -> Self
+    where
+        Self: Sized;
+    fn count(&self, item: &T) -> Self::Output;
+    async fn reset(&self);
+}
+
+struct Numbers(Vec<u32>);
+
+impl Source for Numbers {
+    type Item = u32;
+    fn new() -> Self {
+        Numbers(vec![1, 2, 3])
+    }
+    fn name(&self) -> String {
+        String::from("numbers")
+    }
+    fn items(&self) -> impl Iterator<Item = &u32> {
+        self.0.iter()
+    }
+    async fn fetch(&mut self, index: usize) -> Option<u32> {
+        self.0.get(index).copied()
+    }
+}
+
+struct Prefix(&'static str);
+
+impl Logger for Prefix {
+    fn log<D: Display + ?Sized>(&self, message: &D) -> String {
+        format!("{}{}", self.0, message)
+    }
+    fn into_lines(self) -> Vec<String> {
+        vec![String::from(self.0)]
+    }
+}
+
+impl Label for Prefix {
+    fn label(&self) -> impl Debug + Display {
+        self.0
+    }
+    fn describe<D: Debug + Display + ?Sized>(&self, value: &D) -> String {
+        format!("{}{:?}", self.0, value)
+    }
+    fn width(&self) -> usize {
+        self.0.len()
+    }
+}
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = std::pin::pin!(future);
+    let mut context = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return output;
+        }
+    }
+}
+
+fn sum<S: Source<Item = u32> + ?Sized>(source: &mut S) -> u32 {
+    let total = source.items().sum::<u32>();
+    total + block_on(source.fetch(0)).unwrap_or_default()
+}
+
+#[test]
+fn test_dyn() {
+    let mut source: Box<dyn DynSource<Item = u32>> = Box::new(Numbers::new());
+    assert_eq!(source.name(), "numbers");
+    assert_eq!(source.items().count(), 3);
+    assert_eq!(block_on(source.fetch(1)), Some(2));
+    assert_eq!(block_on(source.fetch(3)), None);
+}
+
+#[test]
+fn test_dyn_original() {
+    let mut source: Box<dyn DynSource<Item = u32>> = Box::new(Numbers::new());
+    assert_eq!(sum(source.as_mut()), 7);
+}
+
+#[test]
+fn test_dyn_erased() {
+    let logger: Box<dyn DynLogger> = Box::new(Prefix("> "));
+    assert_eq!(logger.log(&"hello"), "> hello");
+    assert_eq!(logger.log(&1), "> 1");
+    assert_eq!(logger.into_lines(), vec![String::from("> ")]);
+}
+
+#[test]
+fn test_dyn_dropped() {
+    let label: Box<dyn DynLabel> = Box::new(Prefix("> "));
+    assert_eq!(label.width(), 2);
+}
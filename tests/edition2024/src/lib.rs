@@ -0,0 +1,2 @@
+//! Tests of the `blanket` derives in the 2024 edition, where opaque types
+//! capture all the lifetimes in scope unless given `use<..>` bounds.
@@ -0,0 +1,70 @@
+use std::borrow::Cow;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use blanket::blanket;
+
+#[blanket(derive(Ref, Mut, Box, Rc, Arc, Cow, Fallback))]
+pub trait Bytes {
+    fn bytes(&self) -> impl Iterator<Item = u8>;
+    fn bytes_captured(&self) -> impl Iterator<Item = u8> + use<'_, Self>;
+    fn size(&self) -> impl Into<usize> + use<Self>;
+}
+
+#[blanket(derive(Ref, Box, Fallback))]
+pub trait Tokens<'a> {
+    fn tokens(&self, input: &'a str) -> impl Iterator<Item = &'a str>;
+    fn tokens_captured(&self, input: &'a str) -> impl Iterator<Item = &'a str> + use<'a, Self>;
+}
+
+#[derive(Clone)]
+struct Word(&'static str);
+
+impl Bytes for Word {
+    fn bytes(&self) -> impl Iterator<Item = u8> {
+        self.0.bytes()
+    }
+    fn bytes_captured(&self) -> impl Iterator<Item = u8> + use<'_> {
+        self.0.bytes()
+    }
+    fn size(&self) -> impl Into<usize> + use<> {
+        self.0.len()
+    }
+}
+
+impl<'a> Tokens<'a> for Word {
+    fn tokens(&self, input: &'a str) -> impl Iterator<Item = &'a str> {
+        input.split(self.0)
+    }
+    fn tokens_captured(&self, input: &'a str) -> impl Iterator<Item = &'a str> + use<'a> {
+        let separator: &'static str = self.0;
+        input.split(separator)
+    }
+}
+
+fn count<B: Bytes>(b: B) -> usize {
+    assert_eq!(b.bytes().count(), b.bytes_captured().count());
+    b.size().into()
+}
+
+#[test]
+fn test_bytes() {
+    let mut word = Word("abc");
+    assert_eq!(count(&word), 3);
+    assert_eq!(count(&mut word), 3);
+    assert_eq!(count(Box::new(word.clone())), 3);
+    assert_eq!(count(Rc::new(word.clone())), 3);
+    assert_eq!(count(Arc::new(word.clone())), 3);
+    assert_eq!(count(Cow::Borrowed(&word)), 3);
+    assert_eq!(count((word, Word("de"))), 3);
+}
+
+#[test]
+fn test_tokens() {
+    let words = (Word(" "), Word(","));
+    let input = String::from("a b,c");
+    let tokens = words.tokens(&input).collect::<Vec<_>>();
+    assert_eq!(tokens, words.tokens_captured(&input).collect::<Vec<_>>());
+    assert_eq!(tokens, ["a", "b,c"]);
+    assert_eq!(Box::new(&words.1).tokens(&input).count(), 2);
+}
@@ -0,0 +1,137 @@
+#![allow(unused)]
+
+extern crate blanket;
+use blanket::blanket;
+
+#[blanket(either = StorageEither)]
+pub trait Storage {
+    type Key;
+    fn name(&self) -> String;
+    fn insert(&mut self, key: Self::Key, value: &str) -> Option<String>;
+    fn with_capacity(self, capacity: usize) -> Self;
+    fn into_len(self) -> usize;
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct Memory {
+    values: Vec<(u32, String)>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct Disk {
+    capacity: usize,
+    writes: usize,
+}
+
+impl Storage for Memory {
+    type Key = u32;
+    fn name(&self) -> String {
+        String::from("memory")
+    }
+    fn insert(&mut self, key: u32, value: &str) -> Option<String> {
+        self.values.push((key, value.to_string()));
+        None
+    }
+    fn with_capacity(mut self, capacity: usize) -> Self {
+        self.values.reserve(capacity);
+        self
+    }
+    fn into_len(self) -> usize {
+        self.values.len()
+    }
+}
+
+impl Storage for Disk {
+    type Key = u32;
+    fn name(&self) -> String {
+        String::from("disk")
+    }
+    fn insert(&mut self, _key: u32, value: &str) -> Option<String> {
+        self.writes += 1;
+        Some(value.to_string())
+    }
+    fn with_capacity(self, capacity: usize) -> Self {
+        Disk { capacity, ..self }
+    }
+    fn into_len(self) -> usize {
+        self.writes
+    }
+}
+
+fn open(memory: bool) -> StorageEither<Memory, Disk> {
+    if memory {
+        StorageEither::Left(Memory::default())
+    } else {
+        StorageEither::Right(Disk::default())
+    }
+}
+
+#[test]
+fn test_either() {
+    let mut storage = open(true);
+    assert_eq!(storage.name(), "memory");
+    assert_eq!(storage.insert(1, "a"), None);
+    assert_eq!(storage.into_len(), 1);
+
+    let mut storage = open(false);
+    assert_eq!(storage.name(), "disk");
+    assert_eq!(storage.insert(1, "a"), Some(String::from("a")));
+    match storage.with_capacity(4) {
+        StorageEither::Right(disk) => assert_eq!(disk.capacity, 4),
+        StorageEither::Left(_) => panic!("expected the `Right` variant"),
+    }
+}
+
+mod codecs {
+    pub struct Lz4;
+}
+
+#[blanket(either = Codecs(Gzip, Zstd, codecs::Lz4))]
+pub trait Codec {
+    fn name(&self) -> &'static str;
+    fn level(&mut self, level: u8) -> u8;
+}
+
+pub struct Gzip;
+pub struct Zstd(u8);
+
+impl Codec for Gzip {
+    fn name(&self) -> &'static str {
+        "gzip"
+    }
+    fn level(&mut self, _level: u8) -> u8 {
+        6
+    }
+}
+
+impl Codec for Zstd {
+    fn name(&self) -> &'static str {
+        "zstd"
+    }
+    fn level(&mut self, level: u8) -> u8 {
+        self.0 = level;
+        level
+    }
+}
+
+impl Codec for codecs::Lz4 {
+    fn name(&self) -> &'static str {
+        "lz4"
+    }
+    fn level(&mut self, _level: u8) -> u8 {
+        1
+    }
+}
+
+#[test]
+fn test_closed_set() {
+    let mut codecs = [
+        Codecs::Gzip(Gzip),
+        Codecs::Zstd(Zstd(3)),
+        Codecs::Lz4(codecs::Lz4),
+    ];
+    let names = codecs.iter().map(Codec::name).collect::<Vec<_>>();
+    assert_eq!(names, ["gzip", "zstd", "lz4"]);
+    let levels = codecs.iter_mut().map(|c| c.level(9)).collect::<Vec<_>>();
+    assert_eq!(levels, [6, 9, 1]);
+}
@@ -0,0 +1,90 @@
+#![allow(unused)]
+
+extern crate blanket;
+use blanket::blanket;
+
+mod codecs {
+    pub struct Lz4;
+}
+
+#[blanket(enum_dispatch = Codecs(Gzip, Zstd, codecs::Lz4))]
+pub trait Codec {
+    type Error;
+    fn name(&self) -> &'static str;
+    fn set_level(&mut self, level: u8) -> Result<(), Self::Error>;
+    fn with_level(self, level: u8) -> Self;
+    fn into_level(self: Box<Self>) -> u8;
+}
+
+pub struct Gzip;
+pub struct Zstd(u8);
+
+impl Codec for Gzip {
+    type Error = String;
+    fn name(&self) -> &'static str {
+        "gzip"
+    }
+    fn set_level(&mut self, _level: u8) -> Result<(), String> {
+        Err(String::from("gzip has a fixed level"))
+    }
+    fn with_level(self, _level: u8) -> Self {
+        self
+    }
+    fn into_level(self: Box<Self>) -> u8 {
+        6
+    }
+}
+
+impl Codec for Zstd {
+    type Error = String;
+    fn name(&self) -> &'static str {
+        "zstd"
+    }
+    fn set_level(&mut self, level: u8) -> Result<(), String> {
+        self.0 = level;
+        Ok(())
+    }
+    fn with_level(self, level: u8) -> Self {
+        Zstd(level)
+    }
+    fn into_level(self: Box<Self>) -> u8 {
+        self.0
+    }
+}
+
+impl Codec for codecs::Lz4 {
+    type Error = String;
+    fn name(&self) -> &'static str {
+        "lz4"
+    }
+    fn set_level(&mut self, _level: u8) -> Result<(), String> {
+        Ok(())
+    }
+    fn with_level(self, _level: u8) -> Self {
+        self
+    }
+    fn into_level(self: Box<Self>) -> u8 {
+        1
+    }
+}
+
+#[test]
+fn test_from() {
+    let codecs: [Codecs; 3] = [Gzip.into(), Zstd(3).into(), codecs::Lz4.into()];
+    let names = codecs.iter().map(Codec::name).collect::<Vec<_>>();
+    assert_eq!(names, ["gzip", "zstd", "lz4"]);
+}
+
+#[test]
+fn test_receivers() {
+    let mut codec = Codecs::from(Zstd(3));
+    assert_eq!(codec.set_level(5), Ok(()));
+    assert_eq!(Box::new(codec).into_level(), 5);
+
+    let mut codec = Codecs::from(Gzip);
+    assert!(codec.set_level(5).is_err());
+    assert_eq!(Box::new(codec.with_level(5)).into_level(), 6);
+
+    let codec = Codecs::from(Zstd(3)).with_level(9);
+    assert!(matches!(codec, Codecs::Zstd(Zstd(9))));
+}
@@ -0,0 +1,57 @@
+extern crate blanket;
+use blanket::blanket;
+
+const SEPARATOR: char = ' ';
+
+#[blanket(extract_defaults = visit, derive(Mut))]
+pub trait Visit {
+    fn visit_str(&mut self, s: &str) {
+        for word in s.split(self::SEPARATOR) {
+            self.visit_word(word);
+        }
+    }
+    fn visit_word(&mut self, word: &str) {
+        for c in word.chars() {
+            self.visit_char(c);
+        }
+    }
+    fn visit_char(&mut self, c: char);
+    fn name(&self) -> String {
+        format!("{}{}", Self::kind(), self::SEPARATOR)
+    }
+    fn kind() -> &'static str {
+        "visitor"
+    }
+}
+
+#[derive(Default)]
+struct WordCounter {
+    words: usize,
+    chars: usize,
+}
+
+impl Visit for WordCounter {
+    fn visit_word(&mut self, word: &str) {
+        self.words += 1;
+        visit::visit_word(self, word);
+    }
+    fn visit_char(&mut self, _c: char) {
+        self.chars += 1;
+    }
+}
+
+#[test]
+fn test_extracted() {
+    let mut counter = WordCounter::default();
+    counter.visit_str("Hello big world");
+    assert_eq!(counter.words, 3);
+    assert_eq!(counter.chars, 13);
+    assert_eq!(counter.name(), "visitor ");
+}
+
+#[test]
+fn test_derived() {
+    let mut counter = WordCounter::default();
+    visit::visit_str(&mut &mut counter, "Hello world");
+    assert_eq!(counter.words, 2);
+}
@@ -0,0 +1,9 @@
+extern crate blanket;
+use blanket::blanket;
+
+#[blanket(assert_dyn_compatible)]
+pub trait Shape: Clone {
+    fn area(&self) -> f64;
+}
+
+pub fn main() {}
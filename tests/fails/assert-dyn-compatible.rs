@@ -0,0 +1,18 @@
+extern crate blanket;
+use blanket::blanket;
+
+#[blanket(assert_dyn_compatible)]
+pub trait Shape {
+    const SIDES: usize;
+    fn new() -> Self;
+    fn scale<T: Into<f64>>(&self, factor: T) -> f64;
+    fn same(&self, other: &Self) -> bool;
+    async fn area(&self) -> f64;
+    fn points(&self) -> impl Iterator<Item = (f64, f64)>;
+    fn name(&self) -> &str;
+    fn unit() -> Self
+    where
+        Self: Sized;
+}
+
+pub fn main() {}
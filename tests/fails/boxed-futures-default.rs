@@ -0,0 +1,20 @@
+extern crate blanket;
+use blanket::blanket;
+
+#[blanket(boxed_futures)]
+pub trait Store {
+    async fn ready(&self) -> bool {
+        true
+    }
+    async fn reset(&mut self) {}
+}
+
+#[blanket(boxed_futures)]
+pub trait Cache: Send + Sync {
+    async fn ready(&self) -> bool {
+        true
+    }
+    async fn reset(&mut self) {}
+}
+
+fn main() {}
@@ -0,0 +1,23 @@
+extern crate blanket;
+use blanket::blanket;
+
+#[blanket(boxed_futures(Sync))]
+pub trait Store {
+    async fn get(&self, key: &str) -> Option<u32>;
+}
+
+#[blanket(boxed_futures)]
+pub trait Cache {
+    async fn get(&self, key: &str) -> Option<u32>;
+}
+
+struct Memory;
+
+#[blanket(boxed_futures, derive(Box))]
+impl Cache for Memory {
+    async fn get(&self, _key: &str) -> Option<u32> {
+        None
+    }
+}
+
+fn main() {}
@@ -0,0 +1,22 @@
+extern crate blanket;
+use blanket::blanket;
+
+#[blanket(default = "visitor")]
+pub trait Visitor {
+    fn visit_str(&mut self, s: &str);
+    fn visit_char(&mut self, c: char);
+}
+
+pub mod visitor {
+    use super::Visitor;
+
+    pub fn visit_str<V: Visitor + ?Sized>(v: &mut V, s: &str) {
+        for c in s.chars() {
+            v.visit_char(c);
+        }
+    }
+
+    pub fn visit_char<V: Visitor + ?Sized>(_v: &mut V, _c: u8) {}
+}
+
+pub fn main() {}
@@ -0,0 +1,11 @@
+extern crate blanket;
+use blanket::blanket;
+
+#[blanket(derive(Rc))]
+pub trait Counter {
+    fn get(&self) -> usize;
+    fn increment(&mut self);
+    fn reset(&mut self);
+}
+
+pub fn main() {}
@@ -0,0 +1,7 @@
+extern crate blanket;
+use blanket::blanket;
+
+#[blanket(derive(Box, Ref), derive(Box))]
+pub trait MyTrait {}
+
+pub fn main() {}
@@ -0,0 +1,33 @@
+extern crate blanket;
+use blanket::blanket;
+
+#[blanket(either = PluginEither)]
+pub trait Plugin {
+    fn new() -> Self;
+    fn iter(&self) -> impl Iterator<Item = u8>;
+    fn merge(&mut self, other: Self);
+}
+
+#[blanket(either = Codecs(Gzip, 1))]
+pub trait Codec {}
+
+#[blanket(either = Decoders(Json, Toml))]
+pub trait Decoder {
+    type Output;
+}
+
+pub struct Json;
+pub struct Toml;
+
+impl Decoder for Json {
+    type Output = String;
+}
+
+impl Decoder for Toml {
+    type Output = Vec<u8>;
+}
+
+#[blanket(enum_dispatch = Parsers)]
+pub trait Parser {}
+
+pub fn main() {}
@@ -0,0 +1,9 @@
+extern crate blanket;
+use blanket::blanket;
+
+#[blanket(default = visitor, extract_defaults = visit)]
+pub trait Visitor {
+    fn visit_char(&mut self, _c: char) {}
+}
+
+pub fn main() {}
@@ -0,0 +1,26 @@
+extern crate blanket;
+use blanket::blanket;
+
+#[blanket(derive(Tuple, Fallback))]
+pub trait Store {
+    fn get(&self, key: &str) -> Option<String>;
+}
+
+#[blanket(derive(Fallback))]
+pub trait Client {
+    fn connect(url: &str) -> Result<Self, String>
+    where
+        Self: Sized;
+}
+
+#[blanket(derive(Fallback))]
+pub trait Sender {
+    fn send(&self, body: Vec<u8>) -> Result<(), String>;
+}
+
+#[blanket(derive(Fallback))]
+pub trait Lookup {
+    fn get(&self, key: &str) -> Option<impl std::fmt::Display>;
+}
+
+pub fn main() {}
@@ -0,0 +1,28 @@
+extern crate blanket;
+use blanket::blanket;
+
+#[blanket(derive(Vec))]
+pub trait Listener {
+    fn on_event(&self, event: &str);
+    fn pending(&self) -> usize;
+    #[blanket(combine = all)]
+    fn flush(&self) -> Result<(), String>;
+}
+
+#[blanket(derive(Box))]
+pub trait Counter {
+    #[blanket(combine = default)]
+    fn count(&self) -> usize;
+}
+
+#[blanket(derive(Slice))]
+pub trait Sender {
+    fn send(&self, body: Vec<u8>);
+}
+
+#[blanket(derive(Tuple(max = 2)))]
+pub trait Named {
+    fn new(name: String) -> Self;
+}
+
+pub fn main() {}
@@ -0,0 +1,19 @@
+extern crate blanket;
+use blanket::blanket;
+
+#[blanket(derive(Box(inline = sometimes)))]
+pub trait MyTrait {}
+
+#[blanket(derive(Box(cfg = unix, cfg = windows)))]
+pub trait MyOtherTrait {}
+
+#[blanket(derive(Box(bounds = Send)))]
+pub trait MyLastTrait {}
+
+#[blanket(derive(Box(max = 4)))]
+pub trait MyBoxedTrait {}
+
+#[blanket(derive(Tuple(max = 0)))]
+pub trait MyTupleTrait {}
+
+pub fn main() {}
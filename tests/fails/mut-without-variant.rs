@@ -0,0 +1,11 @@
+extern crate blanket;
+use blanket::blanket;
+
+pub struct Node;
+
+#[blanket(derive(Box))]
+pub trait Visit {
+    fn visit_node(&self, #[blanket(mut)] node: &Node);
+}
+
+pub fn main() {}
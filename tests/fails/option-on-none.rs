@@ -0,0 +1,27 @@
+extern crate blanket;
+use blanket::blanket;
+
+#[blanket(derive(Option))]
+pub trait Plugin {
+    fn bytes(&self) -> impl Iterator<Item = u8>;
+}
+
+#[blanket(derive(Box))]
+pub trait Named {
+    #[blanket(on_none = String::new())]
+    fn name(&self) -> String;
+}
+
+#[blanket(derive(Option))]
+pub trait Source {
+    #[blanket(on_none = std::iter::empty())]
+    fn bytes(&self) -> impl Iterator<Item = u8>;
+}
+
+#[blanket(derive(Option))]
+pub trait Runner {
+    fn run(&self) -> Result<(), String>;
+    fn name(&self) -> &String;
+}
+
+pub fn main() {}
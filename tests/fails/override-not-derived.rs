@@ -0,0 +1,10 @@
+extern crate blanket;
+use blanket::blanket;
+
+#[blanket(derive(Ref))]
+pub trait Identified {
+    #[blanket(override(Arc = arc_id))]
+    fn id(&self) -> usize;
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+extern crate blanket;
+use blanket::blanket;
+
+#[blanket(derive(Box, ref))]
+pub trait MyTrait {}
+
+#[blanket(derive(Bx))]
+pub trait MyOtherTrait {}
+
+#[blanket(derive(Weak))]
+pub trait MyLastTrait {}
+
+pub fn main() {}
@@ -0,0 +1,16 @@
+extern crate blanket;
+use blanket::blanket;
+
+#[blanket(derive(Rc), variant(SendCounter: Send))]
+pub trait Counter {
+    async fn count(&self) -> usize;
+}
+
+#[blanket(variant(SendService: Send))]
+pub trait Service {
+    async fn ready(&self) -> bool {
+        true
+    }
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+extern crate blanket;
+use blanket::visitor;
+
+#[visitor(Visit, prefix = "walk")]
+mod expr {
+    pub struct Expr;
+}
+
+pub fn main() {}
@@ -0,0 +1,121 @@
+#![allow(unused)]
+
+extern crate blanket;
+use blanket::blanket;
+
+use std::collections::HashMap;
+
+#[blanket(derive(Fallback))]
+pub trait Store {
+    type Error;
+    fn name(&self) -> String;
+    fn get(&self, key: &str) -> Option<String>;
+    fn get_mut(&mut self, key: &str) -> Option<&mut String>;
+    fn put(&mut self, key: &str, value: &str) -> Result<(), Self::Error>;
+    fn into_len(self) -> Result<usize, Self::Error>;
+    fn check(url: &str) -> Result<(), Self::Error>;
+    fn send(&self, body: Vec<u8>) -> usize;
+    fn keys(&self) -> impl Iterator<Item = &String> + use<'_, Self>;
+}
+
+#[derive(Default)]
+struct Cache {
+    values: HashMap<String, String>,
+    full: bool,
+}
+
+#[derive(Default)]
+struct Storage {
+    values: HashMap<String, String>,
+}
+
+impl Store for Cache {
+    type Error = String;
+    fn name(&self) -> String {
+        String::from("cache")
+    }
+    fn get(&self, key: &str) -> Option<String> {
+        self.values.get(key).cloned()
+    }
+    fn get_mut(&mut self, key: &str) -> Option<&mut String> {
+        self.values.get_mut(key)
+    }
+    fn put(&mut self, key: &str, value: &str) -> Result<(), String> {
+        if self.full {
+            return Err(String::from("cache is full"));
+        }
+        self.values.insert(key.to_string(), value.to_string());
+        Ok(())
+    }
+    fn into_len(self) -> Result<usize, String> {
+        Err(String::from("cache has no length"))
+    }
+    fn check(url: &str) -> Result<(), String> {
+        Err(format!("cannot cache {}", url))
+    }
+    fn send(&self, body: Vec<u8>) -> usize {
+        body.len()
+    }
+    fn keys(&self) -> impl Iterator<Item = &String> {
+        self.values.keys()
+    }
+}
+
+impl Store for Storage {
+    type Error = String;
+    fn name(&self) -> String {
+        String::from("storage")
+    }
+    fn get(&self, key: &str) -> Option<String> {
+        self.values.get(key).cloned()
+    }
+    fn get_mut(&mut self, key: &str) -> Option<&mut String> {
+        self.values.get_mut(key)
+    }
+    fn put(&mut self, key: &str, value: &str) -> Result<(), String> {
+        self.values.insert(key.to_string(), value.to_string());
+        Ok(())
+    }
+    fn into_len(self) -> Result<usize, String> {
+        Ok(self.values.len())
+    }
+    fn check(url: &str) -> Result<(), String> {
+        Ok(())
+    }
+    fn send(&self, body: Vec<u8>) -> usize {
+        0
+    }
+    fn keys(&self) -> impl Iterator<Item = &String> {
+        self.values.keys()
+    }
+}
+
+#[test]
+fn test_primary() {
+    let mut store = (Cache::default(), Storage::default());
+    assert_eq!(store.name(), "cache");
+    store.put("a", "1").unwrap();
+    assert_eq!(store.get("a"), Some(String::from("1")));
+    assert!(store.1.values.is_empty());
+    assert_eq!(store.send(vec![1, 2]), 2);
+    assert_eq!(store.keys().collect::<Vec<_>>(), ["a"]);
+}
+
+#[test]
+fn test_secondary() {
+    let mut store = (
+        Cache {
+            full: true,
+            ..Default::default()
+        },
+        Storage::default(),
+    );
+    store.put("a", "1").unwrap();
+    assert!(store.0.values.is_empty());
+    assert_eq!(store.get("a"), Some(String::from("1")));
+    store.get_mut("a").unwrap().push('0');
+    assert_eq!(store.1.values["a"], "10");
+    assert_eq!(store.get("b"), None);
+    assert_eq!(<(Cache, Storage)>::check("db://"), Ok(()));
+    assert_eq!(store.into_len(), Ok(1));
+}
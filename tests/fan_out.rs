@@ -0,0 +1,146 @@
+#![allow(unused)]
+
+extern crate blanket;
+use blanket::blanket;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+#[blanket(derive(Ref, Vec, Slice, Array))]
+pub trait Listener {
+    fn on_event(&self, event: &str);
+    #[blanket(combine = all)]
+    fn accepts(&self, event: &str) -> bool;
+    #[blanket(combine = any)]
+    fn is_busy(&self) -> bool;
+    #[blanket(combine = default)]
+    fn pending(&self) -> usize;
+    #[blanket(combine = try)]
+    fn flush(&self) -> Result<(), String>;
+}
+
+struct Recorder {
+    name: &'static str,
+    log: Rc<RefCell<Vec<String>>>,
+    accepts: bool,
+    pending: usize,
+}
+
+impl Listener for Recorder {
+    fn on_event(&self, event: &str) {
+        self.log
+            .borrow_mut()
+            .push(format!("{}: {}", self.name, event));
+    }
+    fn accepts(&self, _event: &str) -> bool {
+        self.log
+            .borrow_mut()
+            .push(format!("{}: accepts", self.name));
+        self.accepts
+    }
+    fn is_busy(&self) -> bool {
+        self.pending > 0
+    }
+    fn pending(&self) -> usize {
+        self.pending
+    }
+    fn flush(&self) -> Result<(), String> {
+        self.log.borrow_mut().push(format!("{}: flush", self.name));
+        if self.accepts {
+            Ok(())
+        } else {
+            Err(format!("{} cannot flush", self.name))
+        }
+    }
+}
+
+fn recorders(log: &Rc<RefCell<Vec<String>>>) -> [Recorder; 3] {
+    let recorder = |name, accepts, pending| Recorder {
+        name,
+        log: log.clone(),
+        accepts,
+        pending,
+    };
+    [
+        recorder("a", true, 0),
+        recorder("b", false, 2),
+        recorder("c", true, 3),
+    ]
+}
+
+fn notify<L: Listener + ?Sized>(listener: &L) {
+    listener.on_event("start");
+}
+
+#[test]
+fn test_vec() {
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let listeners = Vec::from(recorders(&log));
+    notify(&listeners);
+    assert_eq!(*log.borrow(), ["a: start", "b: start", "c: start"]);
+    assert!(listeners.is_busy());
+    assert_eq!(listeners.pending(), 5);
+}
+
+#[test]
+fn test_slice() {
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let listeners = recorders(&log);
+    notify::<[Recorder]>(&listeners[1..]);
+    assert_eq!(*log.borrow(), ["b: start", "c: start"]);
+    assert!(!listeners[..1].is_busy());
+}
+
+#[test]
+fn test_array() {
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let listeners = recorders(&log);
+    notify(&listeners);
+    assert_eq!(*log.borrow(), ["a: start", "b: start", "c: start"]);
+    assert_eq!(listeners.pending(), 5);
+}
+
+#[test]
+fn test_all() {
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let listeners = recorders(&log);
+    assert!(!listeners.accepts("start"));
+    assert_eq!(*log.borrow(), ["a: accepts", "b: accepts", "c: accepts"]);
+    assert!(Vec::<Recorder>::new().accepts("start"));
+}
+
+#[test]
+fn test_try() {
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let listeners = recorders(&log);
+    assert_eq!(listeners.flush(), Err(String::from("b cannot flush")));
+    assert_eq!(*log.borrow(), ["a: flush", "b: flush"]);
+    assert_eq!(listeners[..1].flush(), Ok(()));
+}
+
+#[blanket(derive(Vec, Array))]
+pub trait Sink {
+    fn record(&mut self, value: u32);
+    fn finish(self);
+}
+
+#[derive(Default)]
+struct Total(u32);
+
+impl Sink for Total {
+    fn record(&mut self, value: u32) {
+        self.0 += value;
+    }
+    fn finish(self) {
+        assert!(self.0 > 0);
+    }
+}
+
+#[test]
+fn test_receivers() {
+    let mut sinks = vec![Total::default(), Total(1)];
+    sinks.record(2);
+    assert_eq!(sinks[0].0, 2);
+    assert_eq!(sinks[1].0, 3);
+    sinks.finish();
+}
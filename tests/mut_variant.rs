@@ -0,0 +1,72 @@
+#![allow(unused)]
+
+extern crate blanket;
+use blanket::blanket;
+
+pub struct Node {
+    value: u32,
+    children: Vec<Node>,
+}
+
+#[blanket(mut_variant = VisitMut, extract_defaults = visit, derive(Box, Mut))]
+pub trait Visit {
+    fn visit_node(&self, #[blanket(mut)] node: &Node) {
+        self.visit_value(node.value);
+    }
+    fn visit_value(&self, value: u32);
+}
+
+pub mod visit_mut_helpers {
+    use super::*;
+
+    pub fn visit_children<V: VisitMut + ?Sized>(v: &mut V, node: &mut Node) {
+        for child in node.children.iter_mut() {
+            v.visit_node(child);
+        }
+    }
+}
+
+struct Sum(std::cell::Cell<u32>);
+
+impl Visit for Sum {
+    fn visit_value(&self, value: u32) {
+        self.0.set(self.0.get() + value);
+    }
+}
+
+struct Doubler;
+
+impl VisitMut for Doubler {
+    fn visit_node(&mut self, node: &mut Node) {
+        node.value *= 2;
+        visit_mut_helpers::visit_children(self, node);
+        visit_mut::visit_node(self, node);
+    }
+    fn visit_value(&mut self, _value: u32) {}
+}
+
+fn visit<V: Visit>(v: V, node: &Node) {
+    v.visit_node(node);
+}
+
+fn visit_mut<V: VisitMut>(mut v: V, node: &mut Node) {
+    v.visit_node(node);
+}
+
+#[test]
+fn test_twins() {
+    let mut node = Node {
+        value: 1,
+        children: vec![Node {
+            value: 2,
+            children: Vec::new(),
+        }],
+    };
+    visit_mut(Box::new(Doubler), &mut node);
+    assert_eq!(node.value, 2);
+    assert_eq!(node.children[0].value, 4);
+
+    let mut sum = Sum(Default::default());
+    visit(&mut sum, &node);
+    assert_eq!(sum.0.get(), 2);
+}
@@ -0,0 +1,76 @@
+#![allow(unused)]
+
+extern crate blanket;
+use blanket::blanket;
+
+#[blanket(derive(Option))]
+pub trait Plugin {
+    fn new(name: &str) -> Self;
+    fn name(&self) -> String;
+    fn process(&mut self, input: &mut Vec<u8>);
+    fn find(&self, byte: u8) -> Option<usize>;
+    #[blanket(on_none = input.len())]
+    fn limit(&self, input: &[u8]) -> usize;
+    #[blanket(on_none = Box::new(std::iter::empty()))]
+    fn bytes(&self) -> Box<dyn Iterator<Item = u8> + '_>;
+    fn with_suffix(self, suffix: u8) -> Self;
+}
+
+struct Upper {
+    name: String,
+    suffix: Vec<u8>,
+}
+
+impl Plugin for Upper {
+    fn new(name: &str) -> Self {
+        Upper {
+            name: name.to_string(),
+            suffix: Vec::new(),
+        }
+    }
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+    fn process(&mut self, input: &mut Vec<u8>) {
+        input.make_ascii_uppercase();
+        input.extend_from_slice(&self.suffix);
+    }
+    fn find(&self, byte: u8) -> Option<usize> {
+        self.name.bytes().position(|b| b == byte)
+    }
+    fn limit(&self, _input: &[u8]) -> usize {
+        2
+    }
+    fn bytes(&self) -> Box<dyn Iterator<Item = u8> + '_> {
+        Box::new(self.name.bytes())
+    }
+    fn with_suffix(mut self, suffix: u8) -> Self {
+        self.suffix.push(suffix);
+        self
+    }
+}
+
+#[test]
+fn test_some() {
+    let mut plugin = <Option<Upper>>::new("upper").with_suffix(b'!');
+    assert_eq!(plugin.name(), "upper");
+    assert_eq!(plugin.find(b'p'), Some(1));
+    assert_eq!(plugin.limit(b"abc"), 2);
+    assert_eq!(plugin.bytes().count(), 5);
+    let mut input = b"abc".to_vec();
+    plugin.process(&mut input);
+    assert_eq!(input, b"ABC!");
+}
+
+#[test]
+fn test_none() {
+    let mut plugin: Option<Upper> = None;
+    assert_eq!(plugin.name(), "");
+    assert_eq!(plugin.find(b'p'), None);
+    assert_eq!(plugin.limit(b"abc"), 3);
+    assert_eq!(plugin.bytes().count(), 0);
+    let mut input = b"abc".to_vec();
+    plugin.process(&mut input);
+    assert_eq!(input, b"abc");
+    assert!(plugin.with_suffix(b'!').is_none());
+}
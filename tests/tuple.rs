@@ -0,0 +1,107 @@
+#![allow(unused)]
+
+extern crate blanket;
+use blanket::blanket;
+
+use std::cell::RefCell;
+
+#[blanket(derive(Tuple(max = 4)))]
+pub trait Listener {
+    fn new(name: &'static str) -> Self;
+    fn on_event(&self, log: &RefCell<Vec<String>>, event: &str);
+    fn with_level(self, level: u8) -> Self;
+    fn reset(&mut self);
+    #[blanket(combine = all)]
+    fn accepts(&self, level: u8) -> bool;
+    #[blanket(combine = try)]
+    fn validate() -> Result<(), String>;
+}
+
+#[derive(Debug, PartialEq)]
+struct LogListener {
+    level: u8,
+}
+
+#[derive(Debug, PartialEq)]
+struct MetricsListener {
+    name: &'static str,
+}
+
+impl Listener for LogListener {
+    fn new(_name: &'static str) -> Self {
+        LogListener { level: 0 }
+    }
+    fn on_event(&self, log: &RefCell<Vec<String>>, event: &str) {
+        log.borrow_mut().push(format!("log: {}", event));
+    }
+    fn with_level(self, level: u8) -> Self {
+        LogListener { level }
+    }
+    fn reset(&mut self) {
+        self.level = 0;
+    }
+    fn accepts(&self, level: u8) -> bool {
+        level >= self.level
+    }
+    fn validate() -> Result<(), String> {
+        Ok(())
+    }
+}
+
+impl Listener for MetricsListener {
+    fn new(name: &'static str) -> Self {
+        MetricsListener { name }
+    }
+    fn on_event(&self, log: &RefCell<Vec<String>>, event: &str) {
+        log.borrow_mut().push(format!("{}: {}", self.name, event));
+    }
+    fn with_level(self, _level: u8) -> Self {
+        self
+    }
+    fn reset(&mut self) {}
+    fn accepts(&self, _level: u8) -> bool {
+        true
+    }
+    fn validate() -> Result<(), String> {
+        Err(String::from("metrics are disabled"))
+    }
+}
+
+#[test]
+fn test_fan_out() {
+    let log = RefCell::new(Vec::new());
+    let listeners = (
+        LogListener { level: 0 },
+        MetricsListener { name: "metrics" },
+        LogListener { level: 0 },
+    );
+    listeners.on_event(&log, "start");
+    assert_eq!(
+        *log.borrow(),
+        ["log: start", "metrics: start", "log: start"]
+    );
+}
+
+#[test]
+fn test_self() {
+    let mut listeners = <(LogListener, MetricsListener)>::new("metrics").with_level(3);
+    assert_eq!(
+        listeners,
+        (
+            LogListener { level: 3 },
+            MetricsListener { name: "metrics" }
+        )
+    );
+    assert!(!listeners.accepts(2));
+    listeners.reset();
+    assert!(listeners.accepts(2));
+}
+
+#[test]
+fn test_associated() {
+    assert_eq!(<(LogListener,)>::validate(), Ok(()));
+    assert_eq!(
+        <(LogListener, MetricsListener, LogListener, LogListener)>::validate(),
+        Err(String::from("metrics are disabled"))
+    );
+}
@@ -0,0 +1,137 @@
+#![allow(unused)]
+
+extern crate blanket;
+use blanket::blanket;
+
+use std::future::Future;
+use std::sync::Arc;
+use std::task::Context;
+use std::task::Poll;
+use std::task::Waker;
+
+#[blanket(derive(Box, Arc), variant(SendService: Send + Sync))]
+pub trait Service {
+    type Response;
+    async fn call(&self, request: u32) -> Self::Response;
+    async fn ready(&self) -> bool {
+        true
+    }
+    fn name(&self) -> String;
+}
+
+struct Doubler;
+
+impl SendService for Doubler {
+    type Response = u64;
+    async fn call(&self, request: u32) -> u64 {
+        request as u64 * 2
+    }
+    fn name(&self) -> String {
+        String::from("doubler")
+    }
+}
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = std::pin::pin!(future);
+    let mut context = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return output;
+        }
+    }
+}
+
+fn assert_send<F: Future + Send>(future: F) -> F {
+    future
+}
+
+#[test]
+fn test_variant() {
+    let service = Doubler;
+    assert_eq!(block_on(assert_send(SendService::call(&service, 21))), 42);
+    assert!(block_on(assert_send(SendService::ready(&service))));
+}
+
+#[test]
+fn test_original() {
+    let service = Doubler;
+    assert_eq!(block_on(Service::call(&service, 21)), 42);
+    assert_eq!(Service::name(&service), "doubler");
+}
+
+#[test]
+fn test_derived() {
+    let boxed: Box<Doubler> = Box::new(Doubler);
+    assert_eq!(block_on(assert_send(SendService::call(&boxed, 1))), 2);
+    assert_eq!(block_on(Service::call(&boxed, 2)), 4);
+
+    let shared: Arc<Doubler> = Arc::new(Doubler);
+    assert_eq!(block_on(assert_send(SendService::call(&shared, 3))), 6);
+    assert_eq!(Service::name(&shared), "doubler");
+}
+
+#[blanket(derive(Ref, Arc), variant(SendCounter: Send))]
+pub trait Counter {
+    async fn count(&self) -> usize;
+}
+
+struct Fixed(usize);
+
+impl SendCounter for Fixed {
+    async fn count(&self) -> usize {
+        self.0
+    }
+}
+
+#[test]
+fn test_derived_send() {
+    let fixed = Fixed(3);
+    assert_eq!(block_on(assert_send(SendCounter::count(&&fixed))), 3);
+
+    let shared: Arc<Fixed> = Arc::new(Fixed(4));
+    assert_eq!(block_on(assert_send(SendCounter::count(&shared))), 4);
+    assert_eq!(block_on(Counter::count(&shared)), 4);
+}
+
+#[blanket(variant(StaticTally: Send + Sync + 'static))]
+pub trait Tally {
+    async fn tally(&self) -> usize;
+}
+
+impl StaticTally for Fixed {
+    async fn tally(&self) -> usize {
+        // the future holds a `Cell`, and is therefore `Send` but not `Sync`
+        let cell = std::cell::Cell::new(self.0);
+        std::future::ready(()).await;
+        cell.get()
+    }
+}
+
+#[test]
+fn test_future_bounds() {
+    let fixed = Fixed(5);
+    assert_eq!(block_on(assert_send(StaticTally::tally(&fixed))), 5);
+    assert_eq!(block_on(Tally::tally(&fixed)), 5);
+}
+
+#[blanket(variant(SyncGauge: Send + Sync, futures = Send + Sync))]
+pub trait Gauge {
+    async fn read(&self) -> usize;
+}
+
+impl SyncGauge for Fixed {
+    async fn read(&self) -> usize {
+        self.0
+    }
+}
+
+fn assert_sync<F: Future + Sync>(future: F) -> F {
+    future
+}
+
+#[test]
+fn test_explicit_future_bounds() {
+    let fixed = Fixed(6);
+    assert_eq!(block_on(assert_sync(SyncGauge::read(&fixed))), 6);
+    assert_eq!(block_on(Gauge::read(&fixed)), 6);
+}
@@ -0,0 +1,134 @@
+#![allow(unused)]
+
+extern crate blanket;
+use blanket::visitor;
+
+#[visitor(Visit)]
+pub mod ast {
+    pub struct Program {
+        pub name: String,
+        pub body: Vec<Stmt>,
+    }
+
+    pub enum Stmt {
+        Expr(Expr),
+        Let {
+            name: String,
+            value: Option<Box<Expr>>,
+        },
+        Block(Vec<Stmt>),
+        Empty,
+    }
+
+    pub enum Expr {
+        Lit(Lit),
+        Neg(Box<Expr>),
+        Add(Box<Expr>, Box<Expr>),
+        Ref(Ref<'static>),
+    }
+
+    pub struct Lit(pub u32);
+
+    pub struct Ref<'a> {
+        pub target: &'a Lit,
+    }
+}
+
+#[visitor(Walk, module = walking)]
+mod generic {
+    pub struct Tree<T> {
+        pub value: T,
+        pub children: Vec<Tree<T>>,
+    }
+}
+
+use ast::*;
+
+static ONE: Lit = Lit(1);
+
+#[derive(Default)]
+struct Sum(u32);
+
+impl Visit for Sum {
+    fn visit_lit(&mut self, lit: &Lit) {
+        self.0 += lit.0;
+    }
+}
+
+#[derive(Default)]
+struct Depth {
+    current: usize,
+    max: usize,
+}
+
+impl Visit for Depth {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        self.current += 1;
+        self.max = self.max.max(self.current);
+        ast::visit::visit_stmt(self, stmt);
+        self.current -= 1;
+    }
+}
+
+fn program() -> Program {
+    Program {
+        name: String::from("main"),
+        body: vec![
+            Stmt::Expr(Expr::Lit(Lit(2))),
+            Stmt::Let {
+                name: String::from("x"),
+                value: Some(Box::new(Expr::Add(
+                    Box::new(Expr::Neg(Box::new(Expr::Lit(Lit(3))))),
+                    Box::new(Expr::Ref(Ref { target: &ONE })),
+                ))),
+            },
+            Stmt::Block(vec![
+                Stmt::Empty,
+                Stmt::Block(vec![Stmt::Expr(Expr::Lit(Lit(4)))]),
+            ]),
+        ],
+    }
+}
+
+#[test]
+fn test_sum() {
+    let mut sum = Sum::default();
+    sum.visit_program(&program());
+    assert_eq!(sum.0, 10);
+}
+
+#[test]
+fn test_depth() {
+    let mut depth = Depth::default();
+    depth.visit_program(&program());
+    assert_eq!(depth.max, 3);
+    assert_eq!(depth.current, 0);
+}
+
+#[test]
+fn test_generic() {
+    struct Count(usize);
+    impl generic::Walk for Count {
+        fn visit_tree<T>(&mut self, tree: &generic::Tree<T>) {
+            self.0 += 1;
+            generic::walking::visit_tree(self, tree);
+        }
+    }
+
+    let tree = generic::Tree {
+        value: 'a',
+        children: vec![
+            generic::Tree {
+                value: 'b',
+                children: Vec::new(),
+            },
+            generic::Tree {
+                value: 'c',
+                children: Vec::new(),
+            },
+        ],
+    };
+    let mut count = Count(0);
+    generic::Walk::visit_tree(&mut count, &tree);
+    assert_eq!(count.0, 3);
+}